@@ -0,0 +1,204 @@
+// Per-environment HTTP client settings: proxy, extra root CAs and mTLS identity.
+// Used by every backend request to X (make_api_request and the webhook/replay setup calls).
+// There's no OAuth token exchange in the backend yet: tokens and keys are entered by hand, so
+// a future exchange should build its client with `build_client_for` too.
+
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+
+// A curl --resolve style entry: requests to `host:port` connect to `address` instead.
+// `address` is an IP (or IP:port with the same port). The connection always uses the URL's port,
+// so a mock on another port has to be reached by putting that port in the request URL.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ResolveOverride {
+    pub host: String,
+    pub port: u16,
+    pub address: String,
+}
+
+impl ResolveOverride {
+    fn socket_addr(&self) -> Result<SocketAddr, String> {
+        let addr = self
+            .address
+            .parse::<SocketAddr>()
+            .or_else(|_| self.address.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, self.port)))
+            .map_err(|_| format!("Invalid resolve address '{}' for {}:{}", self.address, self.host, self.port))?;
+        // reqwest connects to the URL's port whatever the override says
+        if addr.port() != self.port {
+            return Err(format!(
+                "Resolve address '{}' for {}:{} can't change the port; use port {} in the request URL instead",
+                self.address,
+                self.host,
+                self.port,
+                addr.port()
+            ));
+        }
+        Ok(addr)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientConfig {
+    // http://, https:// or socks5:// (socks5h:// to resolve through the proxy)
+    pub proxy_url: Option<String>,
+    // Hosts, domains or CIDRs that bypass the proxy, e.g. "localhost", ".corp.example", "10.0.0.0/8"
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+    // PEM encoded root certificates trusted in addition to the system roots
+    #[serde(default)]
+    pub extra_root_certs: Vec<String>,
+    // PEM encoded client certificate chain and PKCS#8 private key for mTLS
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    // DNS overrides applied to requests whose host and port match an entry
+    #[serde(default)]
+    pub resolve: Vec<ResolveOverride>,
+}
+
+impl ClientConfig {
+    fn apply(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, String> {
+        if let Some(proxy_url) = self.proxy_url.as_deref().filter(|u| !u.trim().is_empty()) {
+            let scheme = proxy_url.split("://").next().unwrap_or_default().to_ascii_lowercase();
+            if !["http", "https", "socks5", "socks5h"].contains(&scheme.as_str()) {
+                return Err(format!("Unsupported proxy scheme '{}'", scheme));
+            }
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .map_err(|e| format!("Invalid proxy URL: {}", e))?
+                .no_proxy(reqwest::NoProxy::from_string(&self.no_proxy.join(",")));
+            builder = builder.proxy(proxy);
+        }
+
+        for pem in &self.extra_root_certs {
+            let certs = reqwest::Certificate::from_pem_bundle(pem.as_bytes())
+                .map_err(|e| format!("Invalid root certificate: {}", e))?;
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => {
+                let identity = reqwest::Identity::from_pkcs8_pem(cert.as_bytes(), key.as_bytes())
+                    .map_err(|e| format!("Invalid client certificate or key: {}", e))?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => return Err("Client certificate and key must be provided together".to_string()),
+        }
+
+        Ok(builder)
+    }
+
+    // The resolve entries that apply to a request for `url`
+    pub fn effective_resolves(&self, url: &str) -> Vec<ResolveOverride> {
+        let Ok(parsed) = url::Url::parse(url) else { return Vec::new() };
+        let (Some(host), Some(port)) = (parsed.host_str(), parsed.port_or_known_default()) else {
+            return Vec::new();
+        };
+        self.resolve
+            .iter()
+            .filter(|entry| entry.host.eq_ignore_ascii_case(host) && entry.port == port)
+            .cloned()
+            .collect()
+    }
+
+    // Validates every setting, including resolve entries that don't match any request yet
+    pub fn build_client(&self) -> Result<reqwest::Client, String> {
+        for entry in &self.resolve {
+            entry.socket_addr()?;
+        }
+        self.apply(reqwest::Client::builder())?
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))
+    }
+
+    // Builds a client for requests to `url`, with the matching resolve overrides applied
+    pub fn build_client_for(&self, url: &str) -> Result<reqwest::Client, String> {
+        let mut builder = self.apply(reqwest::Client::builder())?;
+        for entry in self.effective_resolves(url) {
+            builder = builder.resolve(&entry.host, entry.socket_addr()?);
+        }
+        builder
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use std::convert::Infallible;
+
+    fn resolve(host: &str, port: u16, address: &str) -> ResolveOverride {
+        ResolveOverride { host: host.to_string(), port, address: address.to_string() }
+    }
+
+    #[test]
+    fn picks_resolves_matching_host_and_port() {
+        let config = ClientConfig {
+            resolve: vec![
+                resolve("api.twitter.com", 443, "10.0.0.1"),
+                resolve("api.twitter.com", 8443, "10.0.0.2"),
+                resolve("upload.twitter.com", 443, "10.0.0.3"),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(config.effective_resolves("https://API.twitter.com/2/users/me"), [config.resolve[0].clone()]);
+        assert_eq!(config.effective_resolves("https://api.twitter.com:8443/2/users/me"), [config.resolve[1].clone()]);
+        assert!(config.effective_resolves("http://api.twitter.com/2/users/me").is_empty());
+        assert!(config.effective_resolves("not a url").is_empty());
+    }
+
+    #[test]
+    fn rejects_resolve_addresses_that_change_the_port() {
+        assert_eq!(resolve("api.test", 443, "127.0.0.1").socket_addr().unwrap(), ([127, 0, 0, 1], 443).into());
+        assert!(resolve("api.test", 443, "127.0.0.1:443").socket_addr().is_ok());
+        assert!(resolve("api.test", 443, "[::1]:443").socket_addr().is_ok());
+        assert!(resolve("api.test", 443, "127.0.0.1:8080").socket_addr().unwrap_err().contains("can't change the port"));
+        assert!(resolve("api.test", 443, "localhost").socket_addr().unwrap_err().starts_with("Invalid resolve address"));
+
+        let config = ClientConfig { resolve: vec![resolve("api.test", 443, "127.0.0.1:8080")], ..Default::default() };
+        assert!(config.build_client().is_err());
+    }
+
+    #[test]
+    fn validates_proxy_and_identity_settings() {
+        assert!(ClientConfig::default().build_client().is_ok());
+
+        let proxy = |url: &str| ClientConfig { proxy_url: Some(url.to_string()), ..Default::default() };
+        assert!(proxy("socks5h://127.0.0.1:1080").build_client().is_ok());
+        assert!(proxy("   ").build_client().is_ok()); // Blank means no proxy
+        assert_eq!(proxy("ftp://proxy.test").build_client().err().unwrap(), "Unsupported proxy scheme 'ftp'");
+
+        let cert_only = ClientConfig { client_cert: Some("cert".to_string()), ..Default::default() };
+        assert_eq!(
+            cert_only.build_client().err().unwrap(),
+            "Client certificate and key must be provided together"
+        );
+        let garbage = "-----BEGIN CERTIFICATE-----\nbm90IGEgY2VydA==\n-----END CERTIFICATE-----\n";
+        let bad_root = ClientConfig { extra_root_certs: vec![garbage.to_string()], ..Default::default() };
+        assert!(bad_root.build_client().err().unwrap().starts_with("Invalid root certificate"));
+    }
+
+    #[tokio::test]
+    async fn sends_matching_hosts_to_the_override_address() {
+        let make_svc = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|req: Request<Body>| async move {
+                let host = req.headers().get("host").and_then(|h| h.to_str().ok()).unwrap_or_default().to_string();
+                Ok::<_, Infallible>(Response::new(Body::from(host)))
+            }))
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let port = server.local_addr().port();
+        tokio::spawn(server);
+
+        let url = format!("http://api.mock.test:{}/2/users/me", port);
+        let config = ClientConfig { resolve: vec![resolve("api.mock.test", port, "127.0.0.1")], ..Default::default() };
+        let response = config.build_client_for(&url).unwrap().get(&url).send().await.unwrap();
+        // The request still names the original host
+        assert_eq!(response.text().await.unwrap(), format!("api.mock.test:{}", port));
+    }
+}
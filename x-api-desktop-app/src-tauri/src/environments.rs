@@ -0,0 +1,256 @@
+// Environment profiles: where requests go and which headers/client settings they get.
+// Profiles are persisted as JSON in the app data dir.
+
+use crate::client_config::ClientConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+pub const DEFAULT_PROFILE_ID: &str = "prod";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DtabEntry {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentProfile {
+    pub id: String,
+    pub name: String,
+    pub base_url: String,
+    // Injected into every request; request headers with the same name win
+    #[serde(default)]
+    pub default_headers: HashMap<String, String>,
+    // Named Dtab sets; the active one is sent as Dtab-Local ahead of any request Dtabs
+    #[serde(default)]
+    pub dtab_sets: BTreeMap<String, Vec<DtabEntry>>,
+    pub active_dtab_set: Option<String>,
+    // Adds X-B3-Flags: 1 to every request
+    #[serde(default)]
+    pub tracing: bool,
+    // Proxy, CA, mTLS and resolve settings
+    #[serde(default)]
+    pub client: ClientConfig,
+}
+
+impl EnvironmentProfile {
+    fn new(id: &str, name: &str, base_url: &str) -> Self {
+        EnvironmentProfile {
+            id: id.to_string(),
+            name: name.to_string(),
+            base_url: base_url.to_string(),
+            default_headers: HashMap::new(),
+            dtab_sets: BTreeMap::new(),
+            active_dtab_set: None,
+            tracing: false,
+            client: ClientConfig::default(),
+        }
+    }
+
+    fn tfe_staging(id: &str) -> Self {
+        let mut profile = Self::new(id, id, "https://api.twitter.com");
+        profile.default_headers.insert("X-TFE-Experiment-environment".to_string(), id.to_string());
+        profile.default_headers.insert(
+            "X-Decider-Overrides".to_string(),
+            format!("tfe_route:des_apiservice_{}=on", id),
+        );
+        profile
+    }
+
+    // Relative URLs (e.g. "/2/tweets?ids=1") are joined onto the base URL; absolute ones are left alone
+    pub fn resolve_url(&self, url: &str) -> String {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            url.to_string()
+        } else {
+            format!("{}/{}", self.base_url.trim_end_matches('/'), url.trim_start_matches('/'))
+        }
+    }
+
+    // Merges the profile's injected headers with the ones sent for a single request
+    pub fn apply_headers(&self, request_headers: HashMap<String, String>) -> HashMap<String, String> {
+        let mut headers: HashMap<String, String> = self.default_headers.clone();
+        if self.tracing {
+            headers.insert("X-B3-Flags".to_string(), "1".to_string());
+        }
+
+        let profile_dtab = self
+            .active_dtab_set
+            .as_ref()
+            .and_then(|name| self.dtab_sets.get(name))
+            .map(|entries| {
+                entries
+                    .iter()
+                    .map(|d| format!("{}=>{}", d.from, d.to))
+                    .collect::<Vec<_>>()
+                    .join(";")
+            })
+            .filter(|dtab| !dtab.is_empty());
+
+        for (key, value) in request_headers {
+            headers.retain(|existing, _| !existing.eq_ignore_ascii_case(&key));
+            headers.insert(key, value);
+        }
+
+        // Dtab-Local entries are evaluated in order, so request Dtabs go last to take precedence
+        if let Some(profile_dtab) = profile_dtab {
+            let request_dtab = headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("Dtab-Local"))
+                .map(|(k, v)| (k.clone(), v.clone()));
+            match request_dtab {
+                Some((key, value)) => {
+                    headers.insert(key, format!("{};{}", profile_dtab, value));
+                }
+                None => {
+                    headers.insert("Dtab-Local".to_string(), profile_dtab);
+                }
+            }
+        }
+
+        headers
+    }
+}
+
+fn builtin_profiles() -> Vec<EnvironmentProfile> {
+    vec![
+        EnvironmentProfile::new(DEFAULT_PROFILE_ID, "Production", "https://api.twitter.com"),
+        EnvironmentProfile::tfe_staging("staging1"),
+        EnvironmentProfile::tfe_staging("staging2"),
+        EnvironmentProfile::new("local", "Local mock", "http://127.0.0.1:8080"),
+    ]
+}
+
+pub struct EnvironmentStore {
+    profiles: BTreeMap<String, EnvironmentProfile>,
+    path: Option<PathBuf>, // None until the app data dir is known
+}
+
+impl Default for EnvironmentStore {
+    fn default() -> Self {
+        EnvironmentStore {
+            profiles: builtin_profiles().into_iter().map(|p| (p.id.clone(), p)).collect(),
+            path: None,
+        }
+    }
+}
+
+impl EnvironmentStore {
+    // Loads saved profiles from `path`, falling back to the built-in ones
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let mut store = EnvironmentStore::default();
+        if path.exists() {
+            let data = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let saved: Vec<EnvironmentProfile> = serde_json::from_str(&data)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+            store.profiles = saved.into_iter().map(|p| (p.id.clone(), p)).collect();
+        }
+        store.path = Some(path);
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let data = serde_json::to_string_pretty(&self.list()).map_err(|e| e.to_string())?;
+        std::fs::write(path, data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn list(&self) -> Vec<EnvironmentProfile> {
+        self.profiles.values().cloned().collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&EnvironmentProfile> {
+        self.profiles.get(id)
+    }
+
+    // Adds or replaces a profile after checking its base URL and client settings
+    pub fn upsert(&mut self, profile: EnvironmentProfile) -> Result<(), String> {
+        if profile.id.trim().is_empty() {
+            return Err("Profile id must not be empty".to_string());
+        }
+        url::Url::parse(&profile.base_url).map_err(|e| format!("Invalid base URL: {}", e))?;
+        if let Some(name) = &profile.active_dtab_set {
+            if !profile.dtab_sets.contains_key(name) {
+                return Err(format!("Active Dtab set '{}' does not exist", name));
+            }
+        }
+        profile.client.build_client()?;
+        self.profiles.insert(profile.id.clone(), profile);
+        self.save()
+    }
+
+    pub fn remove(&mut self, id: &str) -> Result<(), String> {
+        if id == DEFAULT_PROFILE_ID {
+            return Err("The prod profile cannot be deleted".to_string());
+        }
+        if self.profiles.remove(id).is_none() {
+            return Err(format!("Unknown environment profile '{}'", id));
+        }
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn resolves_relative_urls_against_the_base_url() {
+        let profile = EnvironmentProfile::new("local", "Local mock", "http://127.0.0.1:8080/");
+        assert_eq!(profile.resolve_url("/2/tweets?ids=1"), "http://127.0.0.1:8080/2/tweets?ids=1");
+        assert_eq!(profile.resolve_url("2/users/me"), "http://127.0.0.1:8080/2/users/me");
+        assert_eq!(profile.resolve_url("https://api.x.com/2/users/me"), "https://api.x.com/2/users/me");
+    }
+
+    #[test]
+    fn merges_profile_and_request_headers() {
+        let mut profile = EnvironmentProfile::tfe_staging("staging1");
+        profile.tracing = true;
+        let merged = profile.apply_headers(headers(&[("x-tfe-experiment-environment", "staging2")]));
+        assert_eq!(merged.get("x-tfe-experiment-environment").map(String::as_str), Some("staging2"));
+        assert!(!merged.contains_key("X-TFE-Experiment-environment"));
+        assert_eq!(merged.get("X-B3-Flags").map(String::as_str), Some("1"));
+        assert!(!merged.contains_key("Dtab-Local"));
+    }
+
+    #[test]
+    fn puts_request_dtabs_after_the_active_profile_set() {
+        let mut profile = EnvironmentProfile::new("prod", "Production", "https://api.twitter.com");
+        profile.dtab_sets.insert(
+            "canary".to_string(),
+            vec![
+                DtabEntry { from: "/s/a".to_string(), to: "/s/a-canary".to_string() },
+                DtabEntry { from: "/s/b".to_string(), to: "/s/b-canary".to_string() },
+            ],
+        );
+        profile.dtab_sets.insert("empty".to_string(), Vec::new());
+
+        // Inactive sets aren't sent
+        assert!(!profile.apply_headers(HashMap::new()).contains_key("Dtab-Local"));
+
+        profile.active_dtab_set = Some("canary".to_string());
+        let merged = profile.apply_headers(HashMap::new());
+        assert_eq!(merged.get("Dtab-Local").map(String::as_str), Some("/s/a=>/s/a-canary;/s/b=>/s/b-canary"));
+
+        // The request's own Dtab-Local keeps its name and comes last, so it wins
+        let merged = profile.apply_headers(headers(&[("dtab-local", "/s/a=>/s/a-mine")]));
+        assert_eq!(
+            merged.get("dtab-local").map(String::as_str),
+            Some("/s/a=>/s/a-canary;/s/b=>/s/b-canary;/s/a=>/s/a-mine")
+        );
+        assert_eq!(merged.len(), 1);
+
+        profile.active_dtab_set = Some("empty".to_string());
+        let merged = profile.apply_headers(headers(&[("Dtab-Local", "/s/a=>/s/a-mine")]));
+        assert_eq!(merged.get("Dtab-Local").map(String::as_str), Some("/s/a=>/s/a-mine"));
+    }
+}
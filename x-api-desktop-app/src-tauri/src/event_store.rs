@@ -0,0 +1,375 @@
+// Persistent webhook event history, kept in SQLite in the app data dir so a debugging
+// session survives a restart.
+
+use crate::webhook::activity;
+use crate::webhook::WebhookRequestInfo;
+use base64::{Engine as _, engine::general_purpose};
+use rusqlite::{params, params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS webhook_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    received_at INTEGER NOT NULL,
+    tunnel_id TEXT NOT NULL,
+    endpoint_id TEXT,
+    method TEXT NOT NULL,
+    uri TEXT NOT NULL,
+    headers TEXT NOT NULL,
+    body TEXT NOT NULL,
+    body_text TEXT NOT NULL,
+    signature_valid INTEGER NOT NULL,
+    event_type TEXT,
+    for_user_id TEXT,
+    forward TEXT,
+    replay_job_id TEXT,
+    fingerprint TEXT,
+    duplicate INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS webhook_events_received_at ON webhook_events (received_at);
+CREATE INDEX IF NOT EXISTS webhook_events_event_type ON webhook_events (event_type);
+CREATE INDEX IF NOT EXISTS webhook_events_for_user_id ON webhook_events (for_user_id);
+";
+
+// Columns added after the first release, applied to databases created before them
+const ADDED_COLUMNS: &[(&str, &str)] = &[
+    ("forward", "TEXT"),
+    ("endpoint_id", "TEXT"),
+    ("replay_job_id", "TEXT"),
+    ("fingerprint", "TEXT"),
+    ("duplicate", "INTEGER NOT NULL DEFAULT 0"),
+];
+
+// Same snake_case keys as the WebhookRequestInfo it flattens
+#[derive(Serialize)]
+pub struct StoredWebhookEvent {
+    pub id: i64,
+    pub received_at: u64, // Milliseconds since the Unix epoch
+    pub event_type: Option<String>, // e.g. "tweet_create_events"; None for non-JSON bodies
+    pub for_user_id: Option<String>,
+    #[serde(flatten)]
+    pub request: WebhookRequestInfo,
+}
+
+// Filters for paging through stored events; every field is optional
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EventFilter {
+    pub event_type: Option<String>,
+    pub for_user_id: Option<String>,
+    pub tunnel_id: Option<String>,
+    pub replay_job_id: Option<String>, // Only deliveries from this Account Activity Replay job
+    pub duplicate: Option<bool>, // Only repeats (true) or only first deliveries (false)
+    pub since: Option<u64>, // Inclusive, ms since the Unix epoch
+    pub until: Option<u64>, // Exclusive, ms since the Unix epoch
+    pub search: Option<String>, // Substring match over the URI, headers and body
+    pub offset: u32,
+    pub limit: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct EventPage {
+    pub events: Vec<StoredWebhookEvent>,
+    pub total: u64, // Matching events across all pages
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn like_pattern(search: &str) -> String {
+    let escaped = search.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+impl EventFilter {
+    // WHERE clause and its parameters
+    fn to_sql(&self) -> (String, Vec<rusqlite::types::Value>) {
+        use rusqlite::types::Value;
+        let mut clauses = Vec::new();
+        let mut values = Vec::new();
+        if let Some(event_type) = &self.event_type {
+            clauses.push("event_type = ?");
+            values.push(Value::Text(event_type.clone()));
+        }
+        if let Some(for_user_id) = &self.for_user_id {
+            clauses.push("for_user_id = ?");
+            values.push(Value::Text(for_user_id.clone()));
+        }
+        if let Some(tunnel_id) = &self.tunnel_id {
+            clauses.push("tunnel_id = ?");
+            values.push(Value::Text(tunnel_id.clone()));
+        }
+        if let Some(replay_job_id) = &self.replay_job_id {
+            clauses.push("replay_job_id = ?");
+            values.push(Value::Text(replay_job_id.clone()));
+        }
+        if let Some(duplicate) = self.duplicate {
+            clauses.push("duplicate = ?");
+            values.push(Value::Integer(duplicate as i64));
+        }
+        if let Some(since) = self.since {
+            clauses.push("received_at >= ?");
+            values.push(Value::Integer(since as i64));
+        }
+        if let Some(until) = self.until {
+            clauses.push("received_at < ?");
+            values.push(Value::Integer(until as i64));
+        }
+        if let Some(search) = self.search.as_deref().filter(|s| !s.is_empty()) {
+            clauses.push("(uri LIKE ? ESCAPE '\\' OR headers LIKE ? ESCAPE '\\' OR body_text LIKE ? ESCAPE '\\')");
+            let pattern = like_pattern(search);
+            values.extend([Value::Text(pattern.clone()), Value::Text(pattern.clone()), Value::Text(pattern)]);
+        }
+        let clause = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+        (clause, values)
+    }
+}
+
+const EVENT_COLUMNS: &str =
+    "id, received_at, tunnel_id, event_type, for_user_id, method, uri, headers, body, signature_valid, forward, endpoint_id, replay_job_id, fingerprint, duplicate";
+
+fn read_event(row: &rusqlite::Row) -> rusqlite::Result<StoredWebhookEvent> {
+    let headers: String = row.get(7)?;
+    let body: String = row.get(8)?;
+    let activity = general_purpose::STANDARD.decode(&body).ok().and_then(|bytes| activity::summarize(&bytes));
+    Ok(StoredWebhookEvent {
+        id: row.get(0)?,
+        received_at: row.get::<_, i64>(1)? as u64,
+        event_type: row.get(3)?,
+        for_user_id: row.get(4)?,
+        request: WebhookRequestInfo {
+            tunnel_id: row.get(2)?,
+            endpoint_id: row.get(11)?,
+            method: row.get(5)?,
+            uri: row.get(6)?,
+            headers: serde_json::from_str(&headers).unwrap_or_default(),
+            body,
+            signature_valid: row.get(9)?,
+            activity,
+            forward: row
+                .get::<_, Option<String>>(10)?
+                .and_then(|forward| serde_json::from_str(&forward).ok()),
+            replay_job_id: row.get(12)?,
+            fingerprint: row.get(13)?,
+            duplicate: row.get(14)?,
+        },
+    })
+}
+
+pub struct EventStore {
+    conn: Connection,
+}
+
+impl Default for EventStore {
+    // In-memory until the app data dir is known
+    fn default() -> Self {
+        Self::init(Connection::open_in_memory().expect("in-memory SQLite is always available"))
+            .expect("schema applies to an empty database")
+    }
+}
+
+impl EventStore {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let conn = Connection::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        Self::init(conn)
+    }
+
+    fn init(conn: Connection) -> Result<Self, String> {
+        conn.execute_batch(SCHEMA).map_err(|e| format!("Failed to create event store schema: {}", e))?;
+
+        let existing: Vec<String> = conn
+            .prepare("SELECT name FROM pragma_table_info('webhook_events')")
+            .and_then(|mut stmt| stmt.query_map([], |row| row.get(0))?.collect())
+            .map_err(|e| format!("Failed to read event store schema: {}", e))?;
+        for (column, kind) in ADDED_COLUMNS {
+            if !existing.iter().any(|name| name == column) {
+                conn.execute_batch(&format!("ALTER TABLE webhook_events ADD COLUMN {} {}", column, kind))
+                    .map_err(|e| format!("Failed to migrate event store: {}", e))?;
+            }
+        }
+        Ok(EventStore { conn })
+    }
+
+    // Saves a delivery and returns its id
+    pub fn insert(&self, info: &WebhookRequestInfo) -> Result<i64, String> {
+        let body = general_purpose::STANDARD.decode(&info.body).unwrap_or_default();
+        let summary = info.activity.clone().or_else(|| activity::summarize(&body));
+        let event_type = summary.as_ref().and_then(|s| s.event_type.clone());
+        let for_user_id = summary.and_then(|s| s.for_user_id);
+        let headers = serde_json::to_string(&info.headers).map_err(|e| e.to_string())?;
+        let forward = info.forward.as_ref().map(serde_json::to_string).transpose().map_err(|e| e.to_string())?;
+        self.conn
+            .execute(
+                "INSERT INTO webhook_events
+                    (received_at, tunnel_id, endpoint_id, method, uri, headers, body, body_text, signature_valid, event_type, for_user_id, forward, replay_job_id, fingerprint, duplicate)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                params![
+                    now_millis() as i64,
+                    info.tunnel_id,
+                    info.endpoint_id,
+                    info.method,
+                    info.uri,
+                    headers,
+                    info.body,
+                    String::from_utf8_lossy(&body),
+                    info.signature_valid,
+                    event_type,
+                    for_user_id,
+                    forward,
+                    info.replay_job_id,
+                    info.fingerprint,
+                    info.duplicate,
+                ],
+            )
+            .map_err(|e| format!("Failed to store webhook event: {}", e))?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    fn select(&self, sql: &str, values: Vec<rusqlite::types::Value>) -> Result<Vec<StoredWebhookEvent>, String> {
+        let mut stmt = self.conn.prepare(sql).map_err(|e| format!("Failed to query webhook events: {}", e))?;
+        stmt.query_map(params_from_iter(values.iter()), read_event)
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to read webhook events: {}", e))
+    }
+
+    // Newest first
+    pub fn query(&self, filter: &EventFilter) -> Result<EventPage, String> {
+        let (clause, values) = filter.to_sql();
+
+        let total: i64 = self.conn
+            .query_row(&format!("SELECT COUNT(*) FROM webhook_events {}", clause), params_from_iter(values.iter()), |row| row.get(0))
+            .map_err(|e| format!("Failed to count webhook events: {}", e))?;
+
+        let limit = filter.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        let sql = format!(
+            "SELECT {} FROM webhook_events {} ORDER BY received_at DESC, id DESC LIMIT {} OFFSET {}",
+            EVENT_COLUMNS, clause, limit, filter.offset
+        );
+        let events = self.select(&sql, values)?;
+
+        Ok(EventPage { events, total: total as u64 })
+    }
+
+    // The given events in the order they were received; unknown ids are skipped
+    pub fn get_many(&self, ids: &[i64]) -> Result<Vec<StoredWebhookEvent>, String> {
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        let sql = format!(
+            "SELECT {} FROM webhook_events WHERE id IN ({}) ORDER BY received_at, id",
+            EVENT_COLUMNS, placeholders
+        );
+        self.select(&sql, ids.iter().map(|id| rusqlite::types::Value::Integer(*id)).collect())
+    }
+
+    // Deletes the given ids, or everything matching `filter`. A filter that matches every event
+    // is refused unless `all` is set, so an empty search form can't wipe the store by accident.
+    pub fn delete(&self, ids: Option<&[i64]>, filter: &EventFilter, all: bool) -> Result<usize, String> {
+        let result = match ids {
+            Some(ids) => {
+                let placeholders = vec!["?"; ids.len()].join(", ");
+                self.conn.execute(
+                    &format!("DELETE FROM webhook_events WHERE id IN ({})", placeholders),
+                    params_from_iter(ids.iter()),
+                )
+            }
+            None => {
+                let (clause, values) = filter.to_sql();
+                if clause.is_empty() && !all {
+                    return Err("Refusing to delete every stored event without `all`".to_string());
+                }
+                self.conn.execute(&format!("DELETE FROM webhook_events {}", clause), params_from_iter(values.iter()))
+            }
+        };
+        result.map_err(|e| format!("Failed to delete webhook events: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn delivery(tunnel_id: &str, body: &str) -> WebhookRequestInfo {
+        WebhookRequestInfo {
+            tunnel_id: tunnel_id.to_string(),
+            endpoint_id: None,
+            method: "POST".to_string(),
+            uri: "/webhook".to_string(),
+            headers: HashMap::from([("content-type".to_string(), "application/json".to_string())]),
+            body: general_purpose::STANDARD.encode(body),
+            signature_valid: true,
+            activity: None,
+            forward: None,
+            replay_job_id: None,
+            fingerprint: None,
+            duplicate: false,
+        }
+    }
+
+    fn ids(page: &EventPage) -> Vec<i64> {
+        page.events.iter().map(|event| event.id).collect()
+    }
+
+    #[test]
+    fn filters_searches_and_deletes() {
+        let store = EventStore::default();
+        let tweet = store.insert(&delivery("default", r#"{"for_user_id":"1","tweet_create_events":[{"id_str":"5","text":"50% off","user":{"id":1}}]}"#)).unwrap();
+        let follow = store.insert(&delivery("default", r#"{"for_user_id":"2","follow_events":[]}"#)).unwrap();
+        let other = store.insert(&delivery("other", "not json")).unwrap();
+
+        let all = store.query(&EventFilter::default()).unwrap();
+        assert_eq!(ids(&all), vec![other, follow, tweet]);
+        assert_eq!(all.events[2].event_type.as_deref(), Some("tweet_create_events"));
+        assert_eq!(all.events[2].for_user_id.as_deref(), Some("1"));
+        assert_eq!(all.events[0].event_type, None);
+
+        let by_type = EventFilter { event_type: Some("follow_events".to_string()), ..Default::default() };
+        assert_eq!(ids(&store.query(&by_type).unwrap()), vec![follow]);
+        let by_user = EventFilter { for_user_id: Some("1".to_string()), ..Default::default() };
+        assert_eq!(ids(&store.query(&by_user).unwrap()), vec![tweet]);
+        let by_tunnel = EventFilter { tunnel_id: Some("other".to_string()), ..Default::default() };
+        assert_eq!(ids(&store.query(&by_tunnel).unwrap()), vec![other]);
+        let future = EventFilter { since: Some(now_millis() + 60_000), ..Default::default() };
+        assert_eq!(store.query(&future).unwrap().total, 0);
+
+        // LIKE wildcards in the search text match literally
+        let search = EventFilter { search: Some("50%".to_string()), ..Default::default() };
+        assert_eq!(ids(&store.query(&search).unwrap()), vec![tweet]);
+        let search = EventFilter { search: Some("%".to_string()), ..Default::default() };
+        assert_eq!(ids(&store.query(&search).unwrap()), vec![tweet]);
+
+        let page = store.query(&EventFilter { offset: 1, limit: Some(1), ..Default::default() }).unwrap();
+        assert_eq!((ids(&page), page.total), (vec![follow], 3));
+
+        assert_eq!(store.delete(Some(&[tweet]), &EventFilter::default(), false).unwrap(), 1);
+        assert_eq!(store.delete(None, &by_tunnel, false).unwrap(), 1);
+        assert_eq!(ids(&store.query(&EventFilter::default()).unwrap()), vec![follow]);
+    }
+
+    #[test]
+    fn deletes_everything_only_when_asked() {
+        let store = EventStore::default();
+        store.insert(&delivery("default", "{}")).unwrap();
+        store.insert(&delivery("other", "{}")).unwrap();
+
+        // An empty search string narrows nothing either
+        let blank_search = EventFilter { search: Some(String::new()), ..Default::default() };
+        for filter in [EventFilter::default(), blank_search] {
+            assert!(store.delete(None, &filter, false).is_err());
+            assert_eq!(store.query(&EventFilter::default()).unwrap().total, 2);
+        }
+        assert_eq!(store.delete(None, &EventFilter::default(), true).unwrap(), 2);
+        assert_eq!(store.query(&EventFilter::default()).unwrap().total, 0);
+    }
+}
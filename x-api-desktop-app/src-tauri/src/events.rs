@@ -0,0 +1,197 @@
+// Typed backend -> frontend events.
+// Every event the backend emits is a variant here; `src/bindings/events.ts` is generated
+// from this list so the frontend listens for exactly the names and payloads we send.
+// Regenerate with `UPDATE_BINDINGS=1 cargo test bindings`.
+
+use crate::media_upload::MediaUploadProgress;
+use crate::webhook::generator::GeneratorProgress;
+use crate::webhook::replay::ReplayOutcome;
+use crate::webhook::setup::SetupProgress;
+use crate::webhook::{TunnelMessage, TunnelUrl, TunnelUrlChange, WebhookRequestInfo};
+use std::sync::Arc;
+use tauri::Emitter;
+
+macro_rules! app_events {
+    ($( $variant:ident($payload:ty) => $name:literal, )*) => {
+        #[derive(Clone)]
+        pub enum AppEvent {
+            $( $variant($payload), )*
+        }
+
+        impl AppEvent {
+            // Every (variant, event name, TypeScript payload type), in declaration order
+            #[cfg(test)]
+            fn contract() -> Vec<(&'static str, &'static str, String)> {
+                vec![ $( (stringify!($variant), $name, <$payload as ts_rs::TS>::name()), )* ]
+            }
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $( AppEvent::$variant(_) => $name, )*
+                }
+            }
+
+            fn payload(&self) -> serde_json::Value {
+                match self {
+                    $( AppEvent::$variant(payload) => serde_json::to_value(payload).unwrap_or_default(), )*
+                }
+            }
+        }
+    };
+}
+
+app_events! {
+    NgrokProgress(TunnelMessage) => "ngrok://progress",
+    NgrokUrl(TunnelUrl) => "ngrok://url-obtained",
+    NgrokError(TunnelMessage) => "ngrok://error",
+    NgrokUrlChanged(TunnelUrlChange) => "ngrok://url-changed",
+    NgrokWebhook(Box<WebhookRequestInfo>) => "ngrok://webhook-received",
+    MediaUploadProgress(MediaUploadProgress) => "media://upload-progress",
+    WebhookReplayProgress(ReplayOutcome) => "webhook://replay-progress",
+    WebhookGeneratorProgress(GeneratorProgress) => "webhook://generator-progress",
+    WebhookSetupProgress(SetupProgress) => "webhook://setup-progress",
+}
+
+impl AppEvent {
+    pub fn emit(self, window: &tauri::Window) {
+        if let Err(e) = window.emit(self.name(), self.payload()) {
+            eprintln!("Failed to emit {} event: {}", self.name(), e);
+        }
+    }
+}
+
+// Where code that doesn't hold a window (e.g. the webhook server) sends its events
+pub type EventSink = Arc<dyn Fn(AppEvent) + Send + Sync>;
+
+pub fn window_sink(window: tauri::Window) -> EventSink {
+    Arc::new(move |event: AppEvent| event.emit(&window))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media_upload::UploadStage;
+    use crate::webhook::activity::{ActivityEventSummary, ActivitySummary, ActivityUser};
+    use crate::webhook::ForwardResult;
+    use crate::NgrokTunnelInfo;
+    use std::path::{Path, PathBuf};
+    use ts_rs::TS;
+
+    // lowerCamelCase key for the APP_EVENTS constant, e.g. NgrokProgress -> ngrokProgress
+    fn event_key(variant: &str) -> String {
+        let mut chars = variant.chars();
+        match chars.next() {
+            Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+            None => String::new(),
+        }
+    }
+
+    // Contents of `src/bindings/events.ts`
+    fn typescript_bindings() -> String {
+        let declarations = [
+            crate::webhook::ProbeResult::decl(),
+            NgrokTunnelInfo::decl(),
+            TunnelMessage::decl(),
+            TunnelUrl::decl(),
+            TunnelUrlChange::decl(),
+            ActivityUser::decl(),
+            ActivityEventSummary::decl(),
+            ActivitySummary::decl(),
+            ForwardResult::decl(),
+            WebhookRequestInfo::decl(),
+            ReplayOutcome::decl(),
+            GeneratorProgress::decl(),
+            crate::webhook::generator::GeneratorReport::decl(),
+            crate::webhook::setup::SetupStep::decl(),
+            crate::webhook::setup::StepStatus::decl(),
+            SetupProgress::decl(),
+            UploadStage::decl(),
+            MediaUploadProgress::decl(),
+        ];
+
+        let mut out = String::from("// This file is generated from src-tauri/src/events.rs. Do not edit by hand.\n");
+        out.push_str("// Regenerate with `UPDATE_BINDINGS=1 cargo test bindings` in src-tauri.\n\n");
+        for declaration in declarations {
+            out.push_str(&format!("export {}\n\n", declaration));
+        }
+
+        out.push_str("export const APP_EVENTS = {\n");
+        for (variant, name, _) in AppEvent::contract() {
+            out.push_str(&format!("    {}: \"{}\",\n", event_key(variant), name));
+        }
+        out.push_str("} as const;\n\n");
+
+        out.push_str("export interface AppEventPayloads {\n");
+        for (_, name, payload) in AppEvent::contract() {
+            out.push_str(&format!("    \"{}\": {};\n", name, payload));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn frontend_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../src")
+    }
+
+    fn collect_sources(dir: &Path, out: &mut Vec<PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                collect_sources(&path, out);
+            } else if matches!(path.extension().and_then(|e| e.to_str()), Some("ts" | "tsx")) {
+                out.push(path);
+            }
+        }
+    }
+
+    #[test]
+    fn bindings_are_up_to_date() {
+        let path = frontend_dir().join("bindings/events.ts");
+        let generated = typescript_bindings();
+        if std::env::var_os("UPDATE_BINDINGS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &generated).unwrap();
+        }
+        let checked_in = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            checked_in == generated,
+            "{} is out of date with the Rust event types; run `UPDATE_BINDINGS=1 cargo test bindings`",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn frontend_only_listens_for_known_events() {
+        let known: Vec<&str> = AppEvent::contract().into_iter().map(|(_, name, _)| name).collect();
+        let mut sources = Vec::new();
+        collect_sources(&frontend_dir(), &mut sources);
+
+        // Any listen('literal', ...) must name an event the backend actually emits
+        for source in sources {
+            let text = std::fs::read_to_string(&source).unwrap();
+            for (index, _) in text.match_indices("listen") {
+                let rest = &text[index + "listen".len()..];
+                let rest = rest.strip_prefix('<').map_or(rest, |r| r.split_once('>').map_or(r, |(_, after)| after));
+                let Some(args) = rest.strip_prefix('(') else { continue };
+                let args = args.trim_start();
+                let Some(quote) = args.chars().next().filter(|c| matches!(c, '\'' | '"' | '`')) else { continue };
+                let name = args[1..].split(quote).next().unwrap_or_default();
+                assert!(
+                    known.contains(&name),
+                    "{} listens for '{}', which the backend never emits",
+                    source.display(),
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn event_names_are_unique() {
+        let mut names: Vec<&str> = AppEvent::contract().into_iter().map(|(_, name, _)| name).collect();
+        let total = names.len();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), total);
+    }
+}
@@ -2,27 +2,66 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
-use tauri::Emitter;
-use ngrok::config::TunnelBuilder;
-use ngrok::tunnel::UrlTunnel;
-use futures::stream::StreamExt;
+use std::collections::{HashMap, HashSet};
+use tauri::Manager;
+use futures::future::BoxFuture;
 use std::sync::{Arc, Mutex};
-use hyper::service::{service_fn};
-use hyper::{Body, Request, Response, StatusCode};
-use std::convert::Infallible;
-use base64::{Engine as _, engine::general_purpose};
-use hmac::{Hmac, Mac};
-use sha2::Sha256;
-use hyper::Method;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+mod client_config;
+mod environments;
+mod event_store;
+mod events;
+mod media_upload;
+mod oauth1;
+mod request_body;
+mod webhook;
+
+use client_config::ResolveOverride;
+use environments::{EnvironmentProfile, EnvironmentStore, DEFAULT_PROFILE_ID};
+use event_store::{EventFilter, EventPage, EventStore};
+use events::{AppEvent, EventSink};
+use ts_rs::TS;
+use media_upload::MediaUploadArgs;
+use oauth1::OAuth1Keys;
+use request_body::BodyMode;
+use webhook::crc_check::CrcCheckReport;
+use webhook::dedup::{DuplicateTracker, DEFAULT_DEDUP_WINDOW};
+use webhook::ngrok_options::{NgrokOptions, NgrokOptionsStore};
+use webhook::tunnel_log::{LogLevel, LogPage, LogQuery, LogSource, TunnelLog};
+use webhook::generator::{GeneratorArgs, GeneratorReport};
+use webhook::local_tls::{CertificateInfo, LocalCertificates};
+use webhook::activity_replay::{ReplayJob, ReplayJobs};
+use webhook::replay::{ReplayArgs, ReplayOutcome};
+use webhook::setup::{SetupProgress, SetupStep, StepStatus, TunnelControl, WebhookSetup, XApi};
+use webhook::{
+    EndpointConfig, ForwardConfig, ProbeResult, ProviderConfig, ResponsePolicy, SupervisorConfig, SupervisorUpdate,
+    TunnelMessage, TunnelUrl, TunnelUrlChange, UpdateFn, WebhookContext, DEFAULT_TUNNEL_ID, TUNNEL_DRAIN_TIMEOUT,
+};
 
 // Define the structure for the request payload coming from the frontend
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct ApiRequestArgs {
     method: String,
     url: String,
     headers: HashMap<String, String>,
     body: Option<serde_json::Value>, // Keep incoming body as Value for flexibility
+    #[serde(default)]
+    body_mode: BodyMode, // How `body` is encoded (json, form, raw or file)
+    #[serde(default)]
+    auth_type: Option<String>, // "bearer" | "oauth1a" | "oauth2"
+    oauth1_keys: Option<OAuth1Keys>, // Required when auth_type is "oauth1a"
+    profile_id: Option<String>, // Environment profile to apply; defaults to "prod"
+}
+
+// Connection details shown alongside the response (timing and wire view)
+#[derive(Serialize)]
+struct WireInfo {
+    elapsed_ms: u64,
+    remote_addr: Option<String>, // The address actually connected to
+    resolve_overrides: Vec<ResolveOverride>, // Resolve entries that applied to this request
 }
 
 // Define the structure for the response payload going back to the frontend
@@ -31,6 +70,7 @@ struct ApiResponse {
     status: u16,
     body: String, // <-- CHANGE: Send body as raw string
     headers: HashMap<String, String>,
+    wire: WireInfo,
 }
 
 // Define the structure for the error payload going back to the frontend
@@ -40,42 +80,135 @@ struct ApiError {
     message: String,
     body: Option<String>, // <-- CHANGE: Send error body as optional raw string
     headers: Option<HashMap<String, String>>,
+    wire: Option<WireInfo>,
 }
 
-// Constants for event names
-const NGROK_PROGRESS_EVENT: &str = "ngrok-progress-event";
-const NGROK_URL_EVENT: &str = "ngrok-url-event";
-const NGROK_ERROR_EVENT: &str = "ngrok-error-event";
-const NGROK_WEBHOOK_EVENT: &str = "ngrok-webhook-event";
-
 // --- State Definitions --- 
-#[derive(Clone, Serialize, Default)]
+#[derive(Clone, Serialize, Default, TS)]
 struct NgrokTunnelInfo {
+    tunnel_id: String,
     is_active: bool,
     url: Option<String>,
-    console_log: Vec<String>,
+    #[serde(skip)]
+    #[ts(skip)]
+    log: TunnelLog, // Fetched with get_tunnel_log
+    reconnects: u32, // Sessions reopened after the listener died
+    #[ts(type = "number")]
+    uptime_secs: u64, // Since the current session came up; 0 while reconnecting
+    last_probe: Option<ProbeResult>, // Latest self-CRC check through the public URL
+    #[ts(type = "number")]
+    deliveries: u64, // Webhook deliveries received since the tunnel started
+    #[ts(type = "number")]
+    duplicates: u64, // Of those, repeats of an event already received within the dedup window
+    #[serde(skip)]
+    #[ts(skip)]
+    dedup: DuplicateTracker, // Shared with the listener, which does the counting
+    #[serde(skip)]
+    #[ts(skip)]
+    connected_at: Option<Instant>,
+}
+
+impl NgrokTunnelInfo {
+    // Copy for the frontend with the uptime filled in
+    fn snapshot(&self) -> NgrokTunnelInfo {
+        let uptime_secs = self.connected_at.map_or(0, |at| at.elapsed().as_secs());
+        let counts = self.dedup.counts();
+        NgrokTunnelInfo { uptime_secs, deliveries: counts.deliveries, duplicates: counts.duplicates, ..self.clone() }
+    }
+}
+
+// Everything needed to tear down a running tunnel
+struct TunnelHandle {
+    ctx: Arc<WebhookContext>, // Shared with the listener, for changing response policies
+    shutdown: watch::Sender<bool>,
+    task: tauri::async_runtime::JoinHandle<()>, // The supervisor, which also closes the session
+}
+
+// Marks a tunnel id as starting until dropped, so two starts for the same app can't race
+struct StartingTunnel {
+    starting: Arc<Mutex<HashSet<String>>>,
+    tunnel_id: String,
+}
+
+impl StartingTunnel {
+    fn claim(starting: &Arc<Mutex<HashSet<String>>>, tunnel_id: &str) -> Result<Self, String> {
+        let mut guard = starting.lock().map_err(|e| format!("Mutex lock error: {}", e))?;
+        if !guard.insert(tunnel_id.to_string()) {
+            return Err(format!("Tunnel '{}' is already starting", tunnel_id));
+        }
+        Ok(StartingTunnel { starting: starting.clone(), tunnel_id: tunnel_id.to_string() })
+    }
 }
 
+impl Drop for StartingTunnel {
+    fn drop(&mut self) {
+        if let Ok(mut guard) = self.starting.lock() {
+            guard.remove(&self.tunnel_id);
+        }
+    }
+}
+
+// Status of every app's tunnel, keyed by tunnel id
+type TunnelStatuses = Arc<Mutex<HashMap<String, NgrokTunnelInfo>>>;
+
 struct AppState {
-    ngrok_info: Arc<Mutex<Option<NgrokTunnelInfo>>>,
+    tunnels: TunnelStatuses,
+    tunnel_handles: Arc<tokio::sync::Mutex<HashMap<String, TunnelHandle>>>,
+    starting_tunnels: Arc<Mutex<HashSet<String>>>, // Tunnel ids between open_tunnel's start and its handle being stored
+    environments: Arc<Mutex<EnvironmentStore>>,
+    ngrok_options: Arc<Mutex<NgrokOptionsStore>>, // Saved per tunnel so the public URL stays the same
+    webhook_events: Arc<Mutex<EventStore>>,
+    generator_stop: Arc<Mutex<Option<watch::Sender<bool>>>>, // Set while the event generator runs
+    webhook_setups: Arc<Mutex<HashMap<String, WebhookSetup>>>, // From setup_account_activity, keyed by tunnel id
+    replay_jobs: Arc<ReplayJobs>, // Account Activity Replay jobs, shared with every listener
+    local_certificates: Arc<Mutex<LocalCertificates>>, // For local HTTPS listeners
 }
 
 impl Default for AppState {
     fn default() -> Self {
         AppState {
-            ngrok_info: Arc::new(Mutex::new(None)),
+            tunnels: Arc::new(Mutex::new(HashMap::new())),
+            tunnel_handles: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            starting_tunnels: Arc::new(Mutex::new(HashSet::new())),
+            environments: Arc::new(Mutex::new(EnvironmentStore::default())),
+            ngrok_options: Arc::new(Mutex::new(NgrokOptionsStore::default())),
+            webhook_events: Arc::new(Mutex::new(EventStore::default())),
+            generator_stop: Arc::new(Mutex::new(None)),
+            webhook_setups: Arc::new(Mutex::new(HashMap::new())),
+            replay_jobs: Arc::new(ReplayJobs::default()),
+            local_certificates: Arc::new(Mutex::new(LocalCertificates::default())),
         }
     }
 }
 
-// --- Payload for Webhook Event ---
-#[derive(Clone, Serialize)]
-struct WebhookRequestInfo {
-    method: String,
-    uri: String,
-    headers: HashMap<String, String>,
-    body: String, // Send body as base64 encoded string for simplicity
+impl AppState {
+    fn profile(&self, profile_id: Option<&str>) -> Result<EnvironmentProfile, String> {
+        let profile_id = profile_id.unwrap_or(DEFAULT_PROFILE_ID);
+        self.environments.lock()
+            .map_err(|e| format!("Mutex lock error: {}", e))?
+            .get(profile_id)
+            .cloned()
+            .ok_or_else(|| format!("Unknown environment profile '{}'", profile_id))
+    }
+
+    // Builds an HTTP client for requests to `url` using the profile's client settings
+    fn client_for(&self, profile_id: Option<&str>, url: &str) -> Result<reqwest::Client, String> {
+        self.profile(profile_id)?.client.build_client_for(url)
+    }
+
+    // X API client for webhook setup, pointed at the profile's base URL with its headers
+    fn x_api(&self, profile_id: Option<&str>, bearer_token: String, oauth1_keys: Option<OAuth1Keys>) -> Result<XApi, String> {
+        let profile = self.profile(profile_id)?;
+        Ok(XApi {
+            client: profile.client.build_client_for(&profile.base_url)?,
+            base_url: profile.base_url.clone(),
+            headers: profile.apply_headers(HashMap::new()),
+            bearer_token,
+            oauth1_keys,
+        })
+    }
 }
+
 // --- End State Definitions ---
 
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
@@ -86,8 +219,13 @@ fn greet(name: &str) -> String {
 
 // Tauri command to make the actual API request
 #[tauri::command]
-async fn make_api_request(args: ApiRequestArgs) -> Result<ApiResponse, ApiError> {
-    let client = reqwest::Client::new();
+async fn make_api_request(args: ApiRequestArgs, state: tauri::State<'_, AppState>) -> Result<ApiResponse, ApiError> {
+    let profile = state.profile(args.profile_id.as_deref())
+        .map_err(|message| ApiError { status: 0, message, body: None, headers: None, wire: None })?;
+    let url = profile.resolve_url(&args.url);
+    let resolve_overrides = profile.client.effective_resolves(&url);
+    let client = profile.client.build_client_for(&url)
+        .map_err(|message| ApiError { status: 0, message, body: None, headers: None, wire: None })?;
     let method = match args.method.to_uppercase().as_str() {
         "GET" => reqwest::Method::GET,
         "POST" => reqwest::Method::POST,
@@ -96,32 +234,61 @@ async fn make_api_request(args: ApiRequestArgs) -> Result<ApiResponse, ApiError>
         "PATCH" => reqwest::Method::PATCH,
         "HEAD" => reqwest::Method::HEAD,
         "OPTIONS" => reqwest::Method::OPTIONS,
-        _ => return Err(ApiError { status: 0, message: format!("Unsupported HTTP method: {}", args.method), body: None, headers: None }),
+        _ => return Err(ApiError { status: 0, message: format!("Unsupported HTTP method: {}", args.method), body: None, headers: None, wire: None }),
+    };
+
+    // Prepare body if present (ONLY for methods that have a body)
+    let prepared_body = if method == reqwest::Method::POST || method == reqwest::Method::PUT || method == reqwest::Method::PATCH {
+        request_body::prepare(&args.body_mode, args.body)
+            .await
+            .map_err(|message| ApiError { status: 0, message, body: None, headers: None, wire: None })?
+    } else {
+        None
     };
 
-    let mut request_builder = client.request(method.clone(), &args.url);
+    let mut request_builder = client.request(method.clone(), &url);
 
-    // Check if tracing was requested by the frontend
+    // Check if tracing was requested by the frontend or the profile
     let mut tracing_requested = false;
 
-    // Add headers from frontend request
-    for (key, value) in args.headers {
+    // Add profile headers merged with the ones from the frontend request
+    for (key, value) in profile.apply_headers(args.headers) {
         if key.eq_ignore_ascii_case("X-B3-Flags") && value == "1" {
             tracing_requested = true;
         }
+        // Content-Type is derived from the body mode below
+        if prepared_body.is_some() && key.eq_ignore_ascii_case("Content-Type") {
+            continue;
+        }
         request_builder = request_builder.header(&key, value);
     }
 
-    // Add body if present
-    if let Some(body) = args.body {
-        if method == reqwest::Method::POST || method == reqwest::Method::PUT || method == reqwest::Method::PATCH {
-            request_builder = request_builder.json(&body);
-        }
+    // Sign the request for user-context endpoints; form parameters are part of the signature
+    if args.auth_type.as_deref() == Some("oauth1a") {
+        let keys = args.oauth1_keys.as_ref().ok_or_else(|| ApiError {
+            status: 0,
+            message: "OAuth 1.0a keys are required for this endpoint".to_string(),
+            body: None,
+            headers: None,
+            wire: None,
+        })?;
+        let form_params = prepared_body.as_ref().map(|b| b.form_params.as_slice()).unwrap_or(&[]);
+        let auth_header = oauth1::authorization_header(method.as_str(), &url, keys, form_params)
+            .map_err(|message| ApiError { status: 0, message, body: None, headers: None, wire: None })?;
+        request_builder = request_builder.header(reqwest::header::AUTHORIZATION, auth_header);
+    }
+
+    if let Some(prepared) = prepared_body {
+        request_builder = request_builder
+            .header(reqwest::header::CONTENT_TYPE, prepared.content_type)
+            .body(prepared.bytes);
     }
 
+    let started = std::time::Instant::now();
     match request_builder.send().await {
         Ok(response) => {
             let status = response.status().as_u16();
+            let remote_addr = response.remote_addr().map(|addr| addr.to_string());
             let response_headers = response.headers().clone();
 
             // Convert headers early
@@ -137,15 +304,22 @@ async fn make_api_request(args: ApiRequestArgs) -> Result<ApiResponse, ApiError>
             }
 
             // Attempt to read the body as raw text FIRST
-            match response.text().await {
+            let body_result = response.text().await;
+            let wire = WireInfo {
+                elapsed_ms: started.elapsed().as_millis() as u64,
+                remote_addr,
+                resolve_overrides,
+            };
+            match body_result {
                 Ok(body_text) => {
                     // We have the body text, now check status
-                    if status >= 200 && status < 300 {
+                    if (200..300).contains(&status) {
                         // Success Case: Return ApiResponse with raw body string
                         Ok(ApiResponse {
                              status,
                              body: body_text,
                              headers: headers_map,
+                             wire,
                         })
                     } else {
                          // Error Case: Return ApiError with raw body string
@@ -154,6 +328,7 @@ async fn make_api_request(args: ApiRequestArgs) -> Result<ApiResponse, ApiError>
                             message: format!("API request failed with status {}", status), // Generic message
                             body: Some(body_text),
                             headers: Some(headers_map),
+                            wire: Some(wire),
                         })
                     }
                 }
@@ -165,6 +340,7 @@ async fn make_api_request(args: ApiRequestArgs) -> Result<ApiResponse, ApiError>
                         message: format!("Failed to read response body text: {}", e),
                         body: None, // Indicate body reading failed
                         headers: Some(headers_map), 
+                        wire: Some(wire),
                     })
                 }
             }
@@ -176,281 +352,846 @@ async fn make_api_request(args: ApiRequestArgs) -> Result<ApiResponse, ApiError>
                 message: format!("Request failed: {}", e),
                 body: None,
                 headers: None,
+                wire: Some(WireInfo {
+                    elapsed_ms: started.elapsed().as_millis() as u64,
+                    remote_addr: None,
+                    resolve_overrides,
+                }),
             })
         }
     }
 }
 
+// Command to upload a local media file in chunks, returning the media_id for use in a Tweet body
+#[tauri::command]
+async fn upload_media(
+    window: tauri::Window,
+    args: MediaUploadArgs,
+    profile_id: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let client = state.client_for(profile_id.as_deref(), &args.upload_url())?;
+    media_upload::upload(&client, &args, |progress| {
+        AppEvent::MediaUploadProgress(progress).emit(&window);
+    })
+    .await
+}
+
+// Command to list all environment profiles
+#[tauri::command]
+fn list_environment_profiles(state: tauri::State<'_, AppState>) -> Result<Vec<EnvironmentProfile>, String> {
+    state.environments.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))
+        .map(|guard| guard.list())
+}
+
+// Command to create or replace an environment profile
+#[tauri::command]
+fn save_environment_profile(profile: EnvironmentProfile, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.environments.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))?
+        .upsert(profile)
+}
+
+// Command to delete an environment profile
+#[tauri::command]
+fn delete_environment_profile(profile_id: String, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.environments.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))?
+        .remove(&profile_id)
+}
+
+// Command to page through stored webhook events, newest first
+#[tauri::command]
+fn list_webhook_events(filter: Option<EventFilter>, state: tauri::State<'_, AppState>) -> Result<EventPage, String> {
+    state.webhook_events.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))?
+        .query(&filter.unwrap_or_default())
+}
+
+// Command to delete stored webhook events by id, or everything matching a filter; returns the count removed.
+// Deleting with an empty filter requires `all`.
+#[tauri::command]
+fn delete_webhook_events(
+    ids: Option<Vec<i64>>,
+    filter: Option<EventFilter>,
+    all: Option<bool>,
+    state: tauri::State<'_, AppState>,
+) -> Result<usize, String> {
+    state.webhook_events.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))?
+        .delete(ids.as_deref(), &filter.unwrap_or_default(), all.unwrap_or(false))
+}
+
+// Command to re-send stored webhook events to a URL, in the order they were received
+#[tauri::command]
+async fn replay_webhook_events(
+    window: tauri::Window,
+    args: ReplayArgs,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<ReplayOutcome>, String> {
+    url::Url::parse(&args.target_url).map_err(|e| format!("Invalid target URL: {}", e))?;
+    let events = state.webhook_events.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))?
+        .get_many(&args.event_ids)?;
+    if events.is_empty() {
+        return Err("None of the selected webhook events exist".to_string());
+    }
+
+    let client = reqwest::Client::new();
+    Ok(webhook::replay::replay(&client, &events, &args, |outcome| {
+        AppEvent::WebhookReplayProgress(outcome.clone()).emit(&window);
+    })
+    .await)
+}
+
+// Command to POST signed synthetic Account Activity events to a webhook URL
+#[tauri::command]
+async fn run_webhook_generator(
+    window: tauri::Window,
+    args: GeneratorArgs,
+    state: tauri::State<'_, AppState>,
+) -> Result<GeneratorReport, String> {
+    url::Url::parse(&args.target_url).map_err(|e| format!("Invalid target URL: {}", e))?;
+    let (stop_tx, stop_rx) = watch::channel(false);
+    {
+        let mut guard = state.generator_stop.lock().map_err(|e| format!("Mutex lock error: {}", e))?;
+        if guard.is_some() {
+            return Err("The event generator is already running".to_string());
+        }
+        *guard = Some(stop_tx);
+    }
+
+    let client = reqwest::Client::new();
+    let report = webhook::generator::run(&client, &args, stop_rx, |progress| {
+        AppEvent::WebhookGeneratorProgress(progress).emit(&window);
+    })
+    .await;
+
+    if let Ok(mut guard) = state.generator_stop.lock() {
+        *guard = None;
+    }
+    report
+}
+
+// Command to stop a running event generator
+#[tauri::command]
+fn stop_webhook_generator(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    let guard = state.generator_stop.lock().map_err(|e| format!("Mutex lock error: {}", e))?;
+    match guard.as_ref() {
+        Some(stop) => {
+            let _ = stop.send(true);
+            Ok(())
+        }
+        None => Err("The event generator is not running".to_string()),
+    }
+}
+
+// Command to send a CRC challenge to a webhook URL and check the server's answer
+#[tauri::command]
+async fn check_webhook_crc(
+    url: String,
+    consumer_secret: String,
+    profile_id: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<CrcCheckReport, String> {
+    let client = state.client_for(profile_id.as_deref(), &url)?;
+    webhook::crc_check::check(&client, &url, &consumer_secret).await
+}
+
 // Command to start the ngrok tunnel
 #[tauri::command]
 async fn start_ngrok_webhook(
-    _app_handle: tauri::AppHandle,
     window: tauri::Window,
+    tunnel_id: Option<String>,
     auth_token: String,
     consumer_secret: String,
+    reject_invalid_signatures: Option<bool>,
+    forward: Option<ForwardConfig>,
     state: tauri::State<'_, AppState>,
 ) -> Result<(), String> {
-    let ngrok_state = state.ngrok_info.clone();
-    let window_clone_for_helpers = window.clone();
-
-    let emit_progress = |msg: String| {
-        let state_clone = ngrok_state.clone();
-        if let Ok(mut guard) = state_clone.lock() {
-            if let Some(info) = guard.as_mut() {
-                info.console_log.push(msg.clone());
-            }
-        }
-        if let Err(e) = window_clone_for_helpers.emit(NGROK_PROGRESS_EVENT, Some(msg)) {
-            eprintln!("Failed to emit progress event: {}", e);
-        }
+    let provider = ProviderConfig::Ngrok { auth_token, options: None };
+    let endpoint = EndpointConfig {
+        consumer_secret,
+        reject_invalid_signatures: reject_invalid_signatures.unwrap_or(false),
+        forward,
     };
-    let emit_error = |msg: String| -> Result<(), String> {
-        let error_msg = format!("Ngrok Error: {}", msg);
-        let state_clone = ngrok_state.clone();
-        if let Ok(mut guard) = state_clone.lock() {
-            if let Some(info) = guard.as_mut() {
-                 info.console_log.push(error_msg.clone());
-                 info.is_active = false;
-                 info.url = None;
-            } else {
-                 *guard = Some(NgrokTunnelInfo {
-                     is_active: false,
-                     url: None,
-                     console_log: vec![error_msg.clone()],
-                 });
-            }
-        }
-        if let Err(e) = window_clone_for_helpers.emit(NGROK_ERROR_EVENT, Some(error_msg.clone())) {
-             eprintln!("Failed to emit error event: {}", e);
+    start_webhook_listener(window, tunnel_id, provider, endpoint, None, None, state).await
+}
+
+// Applies `update` to a tunnel's status, if it still has one
+fn update_tunnel(tunnels: &TunnelStatuses, tunnel_id: &str, update: impl FnOnce(&mut NgrokTunnelInfo)) {
+    if let Ok(mut guard) = tunnels.lock() {
+        if let Some(info) = guard.get_mut(tunnel_id) {
+            update(info);
         }
-        Err(error_msg)
-    };
+    }
+}
 
-    {
-        let mut guard = ngrok_state.lock().map_err(|e| format!("Mutex lock error: {}", e))?;
-        *guard = Some(NgrokTunnelInfo { is_active: true, ..Default::default() });
+// Command to start the webhook receiver on any tunnel provider (ngrok, local port or external URL).
+// Each app gets its own tunnel, keyed by `tunnel_id`, so several can run side by side; a single
+// tunnel can also serve several apps at /webhooks/{id} through `endpoints`.
+#[tauri::command]
+async fn start_webhook_listener(
+    window: tauri::Window,
+    tunnel_id: Option<String>, // The app this tunnel receives for; defaults to "default"
+    provider: ProviderConfig,
+    endpoint: EndpointConfig, // Answers every path without an endpoint of its own
+    endpoints: Option<HashMap<String, EndpointConfig>>, // Served at /webhooks/{id}, each with its own secret
+    options: Option<ListenerOptions>,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let tunnel_id = tunnel_id.unwrap_or_else(|| DEFAULT_TUNNEL_ID.to_string());
+    let endpoints = endpoints.unwrap_or_default();
+    open_tunnel(window, tunnel_id, provider, endpoint, endpoints, options.unwrap_or_default(), &state).await.map(|_| ())
+}
+
+// How a tunnel's listener watches itself and its deliveries; every field is optional
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ListenerOptions {
+    probe_interval_secs: Option<u64>, // Self-CRC probe through the public URL; 0 turns it off
+    dedup_window_secs: Option<u64>, // How long a delivery counts as a repeat of an earlier one; 0 turns flagging off
+}
+
+// Starts (or restarts) an app's tunnel and returns its public URL
+async fn open_tunnel(
+    window: tauri::Window,
+    tunnel_id: String,
+    provider: ProviderConfig,
+    endpoint: EndpointConfig,
+    endpoints: HashMap<String, EndpointConfig>,
+    options: ListenerOptions,
+    state: &AppState,
+) -> Result<String, String> {
+    let provider = with_saved_ngrok_options(state, &tunnel_id, provider)?;
+    let provider = with_local_certificate(state, provider)?;
+    // Self-CRC probes reach a local HTTPS listener through its own certificate
+    let client = match &provider {
+        ProviderConfig::LocalTls { .. } => state.local_certificates.lock()
+            .map_err(|e| format!("Mutex lock error: {}", e))?
+            .client()?,
+        _ => reqwest::Client::new(),
+    };
+    for config in std::iter::once(&endpoint).chain(endpoints.values()).filter_map(|e| e.forward.as_ref()) {
+        url::Url::parse(&config.url).map_err(|e| format!("Invalid forward URL: {}", e))?;
     }
 
-    emit_progress("Starting ngrok session...".to_string());
+    let tunnels = state.tunnels.clone();
+    let message = {
+        let tunnel_id = tunnel_id.clone();
+        move |message: String| TunnelMessage { tunnel_id: tunnel_id.clone(), message }
+    };
 
-    let session_builder = ngrok::Session::builder().authtoken(auth_token);
+    // Starting again while this app's tunnel is up restarts it cleanly instead of leaking the old session
+    let _starting = StartingTunnel::claim(&state.starting_tunnels, &tunnel_id)?;
+    let previous = state.tunnel_handles.lock().await.remove(&tunnel_id);
+    if let Some(handle) = previous {
+        AppEvent::NgrokProgress(message("Stopping existing tunnel...".to_string())).emit(&window);
+        shutdown_tunnel(handle).await;
+    }
 
-    let session = match session_builder.connect().await {
-        Ok(s) => {
-            emit_progress("Ngrok session connected.".to_string());
-            s
+    let log = TunnelLog::default();
+    let dedup = DuplicateTracker::new(options.dedup_window_secs.map_or(DEFAULT_DEDUP_WINDOW, Duration::from_secs));
+    let emit_progress = {
+        let log = log.clone();
+        let message = message.clone();
+        let window = window.clone();
+        move |msg: String| {
+            log.record(LogLevel::Info, LogSource::Session, msg.clone());
+            AppEvent::NgrokProgress(message(msg)).emit(&window);
         }
-        Err(e) => return emit_error(format!("Failed to connect session: {}", e)),
+    };
+    let emit_error = |msg: String| -> Result<String, String> {
+        let error_msg = format!("Tunnel error: {}", msg);
+        log.record(LogLevel::Error, LogSource::Session, error_msg.clone());
+        update_tunnel(&tunnels, &tunnel_id, |info| {
+            info.is_active = false;
+            info.url = None;
+        });
+        AppEvent::NgrokError(message(error_msg.clone())).emit(&window);
+        Err(error_msg)
     };
 
-    emit_progress("Starting HTTP tunnel...".to_string());
+    {
+        let mut guard = tunnels.lock().map_err(|e| format!("Mutex lock error: {}", e))?;
+        guard.insert(tunnel_id.clone(), NgrokTunnelInfo {
+            tunnel_id: tunnel_id.clone(),
+            is_active: true,
+            log: log.clone(),
+            dedup: dedup.clone(),
+            ..Default::default()
+        });
+    }
 
-    let listener = match session.http_endpoint().listen().await {
-        Ok(l) => l,
-        Err(e) => return emit_error(format!("Failed to start listener: {}", e)),
+    let tunnel = match provider.clone().into_provider().open(Box::new(emit_progress.clone())).await {
+        Ok(tunnel) => tunnel,
+        Err(e) => return emit_error(e),
     };
+    let url = tunnel.public_url.clone();
 
-    let url = listener.url().to_string();
     emit_progress(format!("Tunnel established at: {}", url));
-
-    if let Ok(mut guard) = ngrok_state.lock() {
-        if let Some(info) = guard.as_mut() {
-            info.url = Some(url.clone());
-        }
+    for id in endpoints.keys() {
+        emit_progress(format!("Endpoint '{}' at: {}/webhooks/{}", id, url, id));
     }
+    update_tunnel(&tunnels, &tunnel_id, |info| {
+        info.url = Some(url.clone());
+        info.connected_at = Some(Instant::now());
+    });
+    AppEvent::NgrokUrl(TunnelUrl { tunnel_id: tunnel_id.clone(), url: url.clone() }).emit(&window);
+
+    let sink = store_webhook_events(state.webhook_events.clone(), log.clone(), events::window_sink(window.clone()));
+    let ctx = Arc::new(WebhookContext {
+        tunnel_id: tunnel_id.clone(),
+        endpoint,
+        endpoints,
+        policies: Default::default(),
+        client,
+        sink: sink.clone(),
+        log: log.clone(),
+        replays: state.replay_jobs.clone(),
+        duplicates: dedup,
+    });
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
+    let config = SupervisorConfig {
+        probe_interval: match options.probe_interval_secs {
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+            None => SupervisorConfig::default().probe_interval,
+        },
+        ..Default::default()
+    };
+    let on_update = supervisor_updates(tunnels.clone(), tunnel_id.clone(), log.clone(), sink.clone());
 
-    if let Err(e) = window.emit(NGROK_URL_EVENT, Some(url)) {
-        eprintln!("Failed to emit URL event: {}", e);
+    let public_url = url.clone();
+    let task_tunnel_id = tunnel_id.clone();
+    let listener_ctx = ctx.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        log.record(LogLevel::Debug, LogSource::Listener, format!("Webhook listener task started at {}", url));
+        webhook::supervise(provider, tunnel, listener_ctx, config, shutdown_rx, on_update).await;
+
+        // Loop ends - task finished
+        let finish_msg = "Listener task finished.".to_string();
+        log.record(LogLevel::Info, LogSource::Listener, finish_msg.clone());
+        update_tunnel(&tunnels, &task_tunnel_id, |info| {
+            info.is_active = false;
+            info.connected_at = None;
+        });
+        sink(AppEvent::NgrokProgress(message(finish_msg)));
+    });
+
+    let replaced = state.tunnel_handles.lock().await.insert(tunnel_id, TunnelHandle {
+        ctx,
+        shutdown: shutdown_tx,
+        task,
+    });
+    // Starts are serialized per tunnel, so this shouldn't happen; stop it properly if it does
+    if let Some(handle) = replaced {
+        shutdown_tunnel(handle).await;
     }
 
-    // Clone necessary items ONCE before the outer task
-    let window_clone_outer = window.clone();
-    let mut listener = listener;
-    let task_state = state.ngrok_info.clone();
-    let consumer_secret_clone = consumer_secret.clone();
-
-    tauri::async_runtime::spawn(async move {
-        let listener_url = listener.url().to_string();
-        println!("Ngrok listener task started for {}", listener_url);
-
-        while let Some(conn_result) = listener.next().await {
-            match conn_result {
-                Ok(conn) => {
-                    // Clone items needed FOR THIS ITERATION
-                    let window_for_iteration = window.clone();
-                    let consumer_secret_for_service = consumer_secret_clone.clone();
-
-                    tokio::spawn(async move {
-                        let window_for_service_move = window_for_iteration.clone();
-                        let captured_consumer_secret = consumer_secret_for_service.clone();
-
-                        let service = service_fn(move |req: Request<Body>| {
-                            let window_for_req_handler = window_for_service_move.clone();
-                            let consumer_secret = captured_consumer_secret.clone();
-
-                            async move {
-                                println!("Handling request: {} {}", req.method(), req.uri());
-
-                                let method = req.method();
-                                let uri = req.uri();
-
-                                // --- Twitter Webhook Logic ---
-                                if method == Method::GET {
-                                    // Handle CRC Check
-                                    let query_params: HashMap<String, String> = uri.query()
-                                        .map(|v| url::form_urlencoded::parse(v.as_bytes()).into_owned().collect())
-                                        .unwrap_or_else(HashMap::new);
-
-                                    if let Some(crc_token) = query_params.get("crc_token") {
-                                        println!("Received CRC check with token: {}", crc_token);
-                                        // Perform HMAC-SHA256
-                                        type HmacSha256 = Hmac<Sha256>; // Define the type alias
-                                        let mut mac = HmacSha256::new_from_slice(consumer_secret.as_bytes())
-                                            .expect("HMAC can take key of any size");
-                                        mac.update(crc_token.as_bytes());
-                                        let result = mac.finalize();
-                                        let code_bytes = result.into_bytes();
-                                        let response_token = format!("sha256={}", general_purpose::STANDARD.encode(&code_bytes));
-
-                                        println!("Generated CRC response: {}", response_token);
-
-                                        // Construct JSON response
-                                        let json_response = serde_json::json!({
-                                            "response_token": response_token
-                                        });
-
-                                        match Response::builder()
-                                            .status(StatusCode::OK)
-                                            .header(hyper::header::CONTENT_TYPE, "application/json")
-                                            .body(Body::from(serde_json::to_string(&json_response).unwrap()))
-                                        {
-                                            Ok(resp) => Ok(resp),
-                                            Err(e) => {
-                                                eprintln!("Failed to build CRC response: {}", e);
-                                                let mut resp = Response::new(Body::from("Internal Server Error"));
-                                                *resp.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                Ok(resp)
-                                            }
-                                        }
-                                    } else {
-                                        println!("GET request received without crc_token");
-                                        let mut resp = Response::new(Body::from("Missing crc_token parameter"));
-                                        *resp.status_mut() = StatusCode::BAD_REQUEST;
-                                        Ok(resp)
-                                    }
-                                } else if method == Method::POST {
-                                    // Handle incoming webhook event (just acknowledge for now)
-                                    println!("Received POST request (webhook event)");
-
-                                    // --- Capture Request Details (Optional - for debugging/display) ---
-                                    let request_method = req.method().to_string();
-                                    let request_uri = req.uri().to_string();
-                                    let headers = req.headers().iter().map(|(k, v)| {
-                                        (k.to_string(), v.to_str().unwrap_or("[invalid header value]").to_string())
-                                    }).collect();
-
-                                    // Read body bytes
-                                    let body_bytes = match hyper::body::to_bytes(req.into_body()).await {
-                                        Ok(bytes) => bytes,
-                                        Err(e) => {
-                                            eprintln!("Failed to read POST request body: {}", e);
-                                            let mut response = Response::new(Body::from(format!("Error reading body: {}", e)));
-                                            *response.status_mut() = StatusCode::BAD_REQUEST;
-                                            return Ok::<_, Infallible>(response);
-                                        }
-                                    };
-                                    // Encode body using Base64
-                                    let body_base64 = general_purpose::STANDARD.encode(&body_bytes);
-
-                                    // --- Create Payload & Emit Event ---
-                                    let payload = WebhookRequestInfo {
-                                        method: request_method,
-                                        uri: request_uri,
-                                        headers,
-                                        body: body_base64,
-                                    };
-                                    if let Err(e) = window_for_req_handler.emit(NGROK_WEBHOOK_EVENT, Some(&payload)) {
-                                         eprintln!("Failed to emit webhook event: {}", e);
-                                    }
-                                    // --- End Optional Capture ---
-
-                                    // Respond with 200 OK
-                                    let mut resp = Response::new(Body::empty());
-                                    *resp.status_mut() = StatusCode::OK;
-                                    Ok(resp)
-                                } else {
-                                    // Handle other methods
-                                    println!("Received {} request - Method Not Allowed", method);
-                                    let mut resp = Response::new(Body::empty());
-                                    *resp.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
-                                    Ok(resp)
-                                }
-                            }
-                        });
-
-                        // Serve the connection
-                        if let Err(err) = hyper::server::conn::Http::new()
-                            .serve_connection(conn, service)
-                            .await
-                        {
-                            eprintln!("Error serving connection: {:?}", err);
-                            // Use window_for_iteration (which was NOT moved into service_fn)
-                            let _ = window_for_iteration.emit(NGROK_ERROR_EVENT, Some(format!("Connection error: {}", err)));
-                        }
-                    });
-                }
-                Err(e) => {
-                    let error_msg = format!("Listener connection error: {}", e);
-                    eprintln!("Ngrok listener connection error: {}", e);
-                    // Update state using the clone passed into the outer task
-                    if let Ok(mut guard) = task_state.lock() {
-                        if let Some(info) = guard.as_mut() {
-                            info.console_log.push(error_msg.clone());
-                            info.is_active = false;
-                        }
-                    }
-                    // Use the clone created BEFORE the outer task
-                    let _ = window_clone_outer.emit(NGROK_ERROR_EVENT, Some(format!("Listener connection error: {}", e)));
-                    break;
+    emit_progress("Tunnel is active.".to_string());
+
+    Ok(public_url)
+}
+
+// Applies what the supervisor reports to the tunnel's status and passes it on as events
+fn supervisor_updates(tunnels: TunnelStatuses, tunnel_id: String, log: TunnelLog, sink: EventSink) -> UpdateFn {
+    Arc::new(move |update: SupervisorUpdate| {
+        let message = |message: String| TunnelMessage { tunnel_id: tunnel_id.clone(), message };
+        let (level, source, text) = match update {
+            SupervisorUpdate::Progress(msg) => (LogLevel::Info, LogSource::Session, msg),
+            SupervisorUpdate::Disconnected(error) => {
+                update_tunnel(&tunnels, &tunnel_id, |info| info.connected_at = None);
+                (LogLevel::Error, LogSource::Session, format!("Tunnel disconnected: {}", error))
+            }
+            SupervisorUpdate::Reconnecting { attempt, delay } => {
+                let text = format!("Reconnecting in {} s (attempt {})...", delay.as_secs_f64(), attempt);
+                (LogLevel::Info, LogSource::Session, text)
+            }
+            SupervisorUpdate::ReconnectFailed(error) => {
+                (LogLevel::Warn, LogSource::Session, format!("Reconnect failed: {}", error))
+            }
+            SupervisorUpdate::Reconnected { previous_url, url } => {
+                update_tunnel(&tunnels, &tunnel_id, |info| {
+                    info.reconnects += 1;
+                    info.url = Some(url.clone());
+                    info.connected_at = Some(Instant::now());
+                });
+                if url != previous_url {
+                    sink(AppEvent::NgrokUrlChanged(TunnelUrlChange {
+                        tunnel_id: tunnel_id.clone(),
+                        previous_url: previous_url.clone(),
+                        url: url.clone(),
+                    }));
+                    sink(AppEvent::NgrokUrl(TunnelUrl { tunnel_id: tunnel_id.clone(), url: url.clone() }));
+                    let text = format!("Reconnected at a new URL: {} (was {}); re-register the webhook with X", url, previous_url);
+                    (LogLevel::Warn, LogSource::Session, text)
+                } else {
+                    (LogLevel::Info, LogSource::Session, format!("Reconnected at {}", url))
                 }
             }
+            SupervisorUpdate::Probe(result) => {
+                let entry = if result.valid {
+                    (LogLevel::Debug, LogSource::Crc, format!("Self CRC check passed in {} ms", result.latency_ms))
+                } else {
+                    (LogLevel::Warn, LogSource::Crc, format!("Self CRC check failed: {}", result.problems.join("; ")))
+                };
+                update_tunnel(&tunnels, &tunnel_id, |info| info.last_probe = Some(result));
+                entry
+            }
+        };
+        log.record(level, source, text.clone());
+        // Passing probes are only logged, not shown in the console as they arrive
+        if level >= LogLevel::Info {
+            sink(AppEvent::NgrokProgress(message(text)));
         }
-        // Loop ends - task finished
-        println!("Ngrok listener task finished for {}", listener_url);
-        let finish_msg = "Ngrok listener task finished.".to_string();
-        // Restore state update on task finish
-        if let Ok(mut guard) = task_state.lock() {
-            if let Some(info) = guard.as_mut() {
-                info.console_log.push(finish_msg.clone());
-                info.is_active = false;
+    })
+}
+
+// Saves ngrok options given for a tunnel, or fills in the ones saved for it
+fn with_saved_ngrok_options(state: &AppState, tunnel_id: &str, provider: ProviderConfig) -> Result<ProviderConfig, String> {
+    let ProviderConfig::Ngrok { auth_token, options } = provider else { return Ok(provider) };
+    let mut store = state.ngrok_options.lock().map_err(|e| format!("Mutex lock error: {}", e))?;
+    if let Some(options) = options {
+        store.upsert(tunnel_id, *options)?;
+    }
+    let options = store.get(tunnel_id).cloned().unwrap_or_default();
+    Ok(ProviderConfig::Ngrok { auth_token, options: Some(Box::new(options)) })
+}
+
+// Loads the local certificate for HTTPS listeners, failing early if none was generated
+fn with_local_certificate(state: &AppState, provider: ProviderConfig) -> Result<ProviderConfig, String> {
+    let ProviderConfig::LocalTls { port, .. } = provider else { return Ok(provider) };
+    let certificates = state.local_certificates.lock().map_err(|e| format!("Mutex lock error: {}", e))?;
+    Ok(ProviderConfig::LocalTls { port, tls: Some(certificates.server_config()?) })
+}
+
+// Command to get the local HTTPS certificate, if one was generated
+#[tauri::command]
+fn get_local_certificate(state: tauri::State<'_, AppState>) -> Result<Option<CertificateInfo>, String> {
+    state.local_certificates.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))?
+        .info()
+}
+
+// Command to issue a new local CA and HTTPS certificate for `hosts` (localhost and loopback
+// addresses by default); running listeners keep the old one until restarted
+#[tauri::command]
+fn generate_local_certificate(hosts: Option<Vec<String>>, state: tauri::State<'_, AppState>) -> Result<CertificateInfo, String> {
+    state.local_certificates.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))?
+        .generate(&hosts.unwrap_or_default())
+}
+
+// Command to save the local CA certificate (PEM) for test clients to trust
+#[tauri::command]
+fn export_local_ca(path: String, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.local_certificates.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))?
+        .export_ca(std::path::Path::new(&path))
+}
+
+// Command to get the ngrok options saved for a tunnel
+#[tauri::command]
+fn get_ngrok_options(tunnel_id: Option<String>, state: tauri::State<'_, AppState>) -> Result<Option<NgrokOptions>, String> {
+    let tunnel_id = tunnel_id.as_deref().unwrap_or(DEFAULT_TUNNEL_ID);
+    state.ngrok_options.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))
+        .map(|guard| guard.get(tunnel_id).cloned())
+}
+
+// Command to save ngrok options for a tunnel, used from its next start
+#[tauri::command]
+fn save_ngrok_options(tunnel_id: Option<String>, options: NgrokOptions, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    let tunnel_id = tunnel_id.as_deref().unwrap_or(DEFAULT_TUNNEL_ID);
+    state.ngrok_options.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))?
+        .upsert(tunnel_id, options)
+}
+
+// Command to forget a tunnel's ngrok options
+#[tauri::command]
+fn delete_ngrok_options(tunnel_id: Option<String>, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    let tunnel_id = tunnel_id.as_deref().unwrap_or(DEFAULT_TUNNEL_ID);
+    state.ngrok_options.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))?
+        .remove(tunnel_id)
+}
+
+// Saves every delivery to the event store before passing events on
+fn store_webhook_events(store: Arc<Mutex<EventStore>>, log: TunnelLog, sink: EventSink) -> EventSink {
+    Arc::new(move |event: AppEvent| {
+        if let AppEvent::NgrokWebhook(info) = &event {
+            let stored = store.lock()
+                .map_err(|e| format!("Mutex lock error: {}", e))
+                .and_then(|store| store.insert(info));
+            if let Err(e) = stored {
+                log.record(LogLevel::Error, LogSource::Delivery, format!("Failed to store webhook event: {}", e));
             }
         }
-        // Use the clone created BEFORE the outer task
-        let _ = window_clone_outer.emit(NGROK_PROGRESS_EVENT, Some(finish_msg));
-    });
+        sink(event)
+    })
+}
+
+// Extra time the supervisor gets to close the provider session after draining
+const SESSION_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
 
-    emit_progress("Ngrok tunnel is active.".to_string());
+// Stops the accept loop, waits for in-flight connections to drain and the session to close
+async fn shutdown_tunnel(handle: TunnelHandle) {
+    let TunnelHandle { shutdown, mut task, .. } = handle;
+    let _ = shutdown.send(true);
+    // The task itself bounds the drain and closes the session; this only guards against it hanging
+    if tokio::time::timeout(TUNNEL_DRAIN_TIMEOUT + SESSION_CLOSE_TIMEOUT, &mut task).await.is_err() {
+        task.abort();
+    }
+}
+
+// Command to stop one app's tunnel
+#[tauri::command]
+async fn stop_ngrok_webhook(
+    window: tauri::Window,
+    tunnel_id: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let tunnel_id = tunnel_id.unwrap_or_else(|| DEFAULT_TUNNEL_ID.to_string());
+    close_tunnel(&window, tunnel_id, &state).await
+}
+
+async fn close_tunnel(window: &tauri::Window, tunnel_id: String, state: &AppState) -> Result<(), String> {
+    let handle = state.tunnel_handles.lock().await.remove(&tunnel_id);
+    let Some(handle) = handle else {
+        return Err(format!("No tunnel is running for '{}'", tunnel_id));
+    };
+
+    shutdown_tunnel(handle).await;
+
+    {
+        let mut guard = state.tunnels.lock().map_err(|e| format!("Mutex lock error: {}", e))?;
+        guard.remove(&tunnel_id);
+    }
 
+    AppEvent::NgrokProgress(TunnelMessage { tunnel_id, message: "Tunnel stopped.".to_string() }).emit(window);
     Ok(())
 }
 
-// Command to get current status
+// An app's tunnel as the Account Activity setup starts and stops it
+struct AppTunnel<'a> {
+    window: tauri::Window,
+    tunnel_id: String,
+    listener: Option<(ProviderConfig, EndpointConfig)>, // How to start it; None if it's only stopped
+    state: &'a AppState,
+}
+
+impl TunnelControl for AppTunnel<'_> {
+    fn start(&self) -> BoxFuture<'_, Result<String, String>> {
+        let Some((provider, endpoint)) = self.listener.clone() else {
+            return Box::pin(async { Err("No listener settings to start the tunnel with".to_string()) });
+        };
+        let window = self.window.clone();
+        Box::pin(open_tunnel(window, self.tunnel_id.clone(), provider, endpoint, HashMap::new(), ListenerOptions::default(), self.state))
+    }
+
+    fn stop(&self) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(close_tunnel(&self.window, self.tunnel_id.clone(), self.state))
+    }
+}
+
+fn emit_setup_progress(window: &tauri::Window, tunnel_id: &str) -> impl Fn(SetupStep, StepStatus, Option<String>) + Send + Sync {
+    let window = window.clone();
+    let tunnel_id = tunnel_id.to_string();
+    move |step, status, message| {
+        let progress = SetupProgress { tunnel_id: tunnel_id.clone(), step, status, message };
+        AppEvent::WebhookSetupProgress(progress).emit(&window);
+    }
+}
+
+// Arguments for `setup_account_activity`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountActivitySetupArgs {
+    tunnel_id: Option<String>,
+    provider: ProviderConfig,
+    endpoint: EndpointConfig,
+    webhook_path: Option<String>, // Appended to the tunnel URL; defaults to "/webhook"
+    bearer_token: String, // App-only, for registering the webhook
+    oauth1_keys: OAuth1Keys, // The app owner's, for subscribing
+    profile_id: Option<String>,
+    crc_timeout_secs: Option<u64>,
+}
+
+// Command to start the tunnel, register it as the app's webhook, wait for X to accept the CRC
+// response and subscribe the app owner, undoing what was done if a step fails
 #[tauri::command]
-fn get_ngrok_status(state: tauri::State<'_, AppState>) -> Result<Option<NgrokTunnelInfo>, String> {
-    state.ngrok_info.lock()
+async fn setup_account_activity(
+    window: tauri::Window,
+    args: AccountActivitySetupArgs,
+    state: tauri::State<'_, AppState>,
+) -> Result<WebhookSetup, String> {
+    let tunnel_id = args.tunnel_id.unwrap_or_else(|| DEFAULT_TUNNEL_ID.to_string());
+    let api = state.x_api(args.profile_id.as_deref(), args.bearer_token, Some(args.oauth1_keys))?;
+    let tunnel = AppTunnel {
+        window: window.clone(),
+        tunnel_id: tunnel_id.clone(),
+        listener: Some((args.provider, args.endpoint)),
+        state: &state,
+    };
+    let webhook_path = args.webhook_path.unwrap_or_else(|| "/webhook".to_string());
+    let crc_timeout = args.crc_timeout_secs.map_or(webhook::setup::DEFAULT_CRC_TIMEOUT, Duration::from_secs);
+    let progress = emit_setup_progress(&window, &tunnel_id);
+
+    let setup = webhook::setup::setup(&api, &tunnel, &tunnel_id, &webhook_path, crc_timeout, &progress).await?;
+    state.webhook_setups.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))?
+        .insert(tunnel_id, setup.clone());
+    Ok(setup)
+}
+
+// Arguments for `teardown_account_activity`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountActivityTeardownArgs {
+    tunnel_id: Option<String>,
+    bearer_token: String,
+    profile_id: Option<String>,
+    setup: Option<WebhookSetup>, // Defaults to what setup_account_activity created for the tunnel
+}
+
+// Command to unsubscribe, delete the webhook and stop the tunnel, reporting every step that failed
+#[tauri::command]
+async fn teardown_account_activity(
+    window: tauri::Window,
+    args: AccountActivityTeardownArgs,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let tunnel_id = args.tunnel_id.unwrap_or_else(|| DEFAULT_TUNNEL_ID.to_string());
+    let saved = state.webhook_setups.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))?
+        .get(&tunnel_id)
+        .cloned();
+    let setup = args.setup.or(saved).ok_or_else(|| format!("No webhook setup is known for '{}'", tunnel_id))?;
+    let api = state.x_api(args.profile_id.as_deref(), args.bearer_token, None)?;
+    let tunnel = AppTunnel {
+        window: window.clone(),
+        tunnel_id: tunnel_id.clone(),
+        listener: None,
+        state: &state,
+    };
+    let progress = emit_setup_progress(&window, &tunnel_id);
+
+    let result = webhook::setup::teardown(&api, &tunnel, &setup, &progress).await;
+    state.webhook_setups.lock()
+        .map_err(|e| format!("Mutex lock error: {}", e))?
+        .remove(&tunnel_id);
+    result
+}
+
+// Arguments for `request_activity_replay`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ActivityReplayArgs {
+    tunnel_id: Option<String>, // Tunnel whose deliveries are tagged with the job
+    webhook_id: Option<String>, // Defaults to the webhook setup_account_activity registered for the tunnel
+    from: u64, // Window start, ms since the Unix epoch
+    to: u64, // Window end, ms since the Unix epoch
+    bearer_token: String,
+    profile_id: Option<String>,
+}
+
+// Command to have X redeliver a webhook's events from a past time window, e.g. to backfill after
+// an outage; deliveries it produces are tagged with the job id
+#[tauri::command]
+async fn request_activity_replay(args: ActivityReplayArgs, state: tauri::State<'_, AppState>) -> Result<ReplayJob, String> {
+    let tunnel_id = args.tunnel_id.unwrap_or_else(|| DEFAULT_TUNNEL_ID.to_string());
+    webhook::activity_replay::validate_window(args.from, args.to)?;
+    let webhook_id = match args.webhook_id {
+        Some(webhook_id) => webhook_id,
+        None => state.webhook_setups.lock()
+            .map_err(|e| format!("Mutex lock error: {}", e))?
+            .get(&tunnel_id)
+            .map(|setup| setup.webhook_id.clone())
+            .ok_or_else(|| format!("No webhook id given and none registered for '{}'", tunnel_id))?,
+    };
+
+    let api = state.x_api(args.profile_id.as_deref(), args.bearer_token, None)?;
+    let job_id = api.request_replay(&webhook_id, args.from, args.to).await?;
+    let job = ReplayJob::started(job_id, tunnel_id, webhook_id, args.from, args.to);
+    state.replay_jobs.add(job.clone())?;
+    Ok(job)
+}
+
+// Command to list Account Activity Replay jobs, newest first, optionally for one tunnel
+#[tauri::command]
+fn list_activity_replays(tunnel_id: Option<String>, state: tauri::State<'_, AppState>) -> Result<Vec<ReplayJob>, String> {
+    state.replay_jobs.list(tunnel_id.as_deref())
+}
+
+// Command to change how a running tunnel answers one endpoint (None for the default endpoint),
+// e.g. to simulate failures; a default policy answers normally again
+#[tauri::command]
+async fn set_webhook_response_policy(
+    tunnel_id: Option<String>,
+    endpoint_id: Option<String>,
+    policy: ResponsePolicy,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let ctx = running_context(&state, tunnel_id, endpoint_id.as_deref()).await?;
+    ctx.policies.set(endpoint_id, policy)
+}
+
+// Command to get the response policy an endpoint currently has
+#[tauri::command]
+async fn get_webhook_response_policy(
+    tunnel_id: Option<String>,
+    endpoint_id: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<ResponsePolicy, String> {
+    let ctx = running_context(&state, tunnel_id, endpoint_id.as_deref()).await?;
+    Ok(ctx.policies.get(endpoint_id.as_deref()))
+}
+
+// Context of a running tunnel, checking that it serves `endpoint_id`
+async fn running_context(
+    state: &AppState,
+    tunnel_id: Option<String>,
+    endpoint_id: Option<&str>,
+) -> Result<Arc<WebhookContext>, String> {
+    let tunnel_id = tunnel_id.unwrap_or_else(|| DEFAULT_TUNNEL_ID.to_string());
+    let handles = state.tunnel_handles.lock().await;
+    let handle = handles.get(&tunnel_id).ok_or_else(|| format!("No tunnel is running for '{}'", tunnel_id))?;
+    if let Some(id) = endpoint_id.filter(|id| !handle.ctx.endpoints.contains_key(*id)) {
+        return Err(format!("Tunnel '{}' has no endpoint '{}'", tunnel_id, id));
+    }
+    Ok(handle.ctx.clone())
+}
+
+// Command to get the current status of one app's tunnel
+#[tauri::command]
+fn get_ngrok_status(tunnel_id: Option<String>, state: tauri::State<'_, AppState>) -> Result<Option<NgrokTunnelInfo>, String> {
+    let tunnel_id = tunnel_id.as_deref().unwrap_or(DEFAULT_TUNNEL_ID);
+    state.tunnels.lock()
         .map_err(|e| format!("Mutex lock error: {}", e))
-        .map(|guard| guard.clone())
+        .map(|guard| guard.get(tunnel_id).map(NgrokTunnelInfo::snapshot))
+}
+
+// The log of an app's tunnel, if it was started since the app launched
+fn tunnel_log(state: &AppState, tunnel_id: Option<String>) -> Result<Option<TunnelLog>, String> {
+    let tunnel_id = tunnel_id.as_deref().unwrap_or(DEFAULT_TUNNEL_ID);
+    let guard = state.tunnels.lock().map_err(|e| format!("Mutex lock error: {}", e))?;
+    Ok(guard.get(tunnel_id).map(|info| info.log.clone()))
+}
+
+// Command to fetch a range of a tunnel's console log, optionally filtered by level or source
+#[tauri::command]
+fn get_tunnel_log(
+    tunnel_id: Option<String>,
+    query: Option<LogQuery>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<LogPage>, String> {
+    match tunnel_log(&state, tunnel_id)? {
+        Some(log) => log.query(&query.unwrap_or_default()).map(Some),
+        None => Ok(None),
+    }
+}
+
+// Command to write a tunnel's console log to a file as JSON Lines; returns the entry count
+#[tauri::command]
+fn export_tunnel_log(
+    tunnel_id: Option<String>,
+    path: String,
+    query: Option<LogQuery>,
+    state: tauri::State<'_, AppState>,
+) -> Result<usize, String> {
+    let log = tunnel_log(&state, tunnel_id)?.ok_or_else(|| "This tunnel has no log yet".to_string())?;
+    log.export(std::path::Path::new(&path), &query.unwrap_or_default())
+}
+
+// Command to list every app's tunnel, ordered by tunnel id
+#[tauri::command]
+fn list_webhook_tunnels(state: tauri::State<'_, AppState>) -> Result<Vec<NgrokTunnelInfo>, String> {
+    let guard = state.tunnels.lock().map_err(|e| format!("Mutex lock error: {}", e))?;
+    let mut tunnels: Vec<NgrokTunnelInfo> = guard.values().map(NgrokTunnelInfo::snapshot).collect();
+    tunnels.sort_by(|a, b| a.tunnel_id.cmp(&b.tunnel_id));
+    Ok(tunnels)
 }
 
 fn main() {
     tauri::Builder::default()
         .manage(AppState::default())
+        .setup(|app| {
+            // Load saved environment profiles now that the app data dir is known
+            let path = app.path().app_data_dir()?.join("environments.json");
+            match EnvironmentStore::load(path) {
+                Ok(store) => {
+                    if let Ok(mut guard) = app.state::<AppState>().environments.lock() {
+                        *guard = store;
+                    }
+                }
+                Err(e) => eprintln!("Failed to load environment profiles: {}", e),
+            }
+
+            // Saved ngrok options keep each app's webhook URL stable across restarts
+            let path = app.path().app_data_dir()?.join("ngrok_options.json");
+            match NgrokOptionsStore::load(path) {
+                Ok(store) => {
+                    if let Ok(mut guard) = app.state::<AppState>().ngrok_options.lock() {
+                        *guard = store;
+                    }
+                }
+                Err(e) => eprintln!("Failed to load ngrok options: {}", e),
+            }
+
+            // Local HTTPS certificates live next to the other app data
+            if let Ok(mut guard) = app.state::<AppState>().local_certificates.lock() {
+                *guard = LocalCertificates::new(app.path().app_data_dir()?.join("local_tls"));
+            }
+
+            // Keep received webhooks across restarts
+            let path = app.path().app_data_dir()?.join("webhook_events.sqlite3");
+            match EventStore::open(&path) {
+                Ok(store) => {
+                    if let Ok(mut guard) = app.state::<AppState>().webhook_events.lock() {
+                        *guard = store;
+                    }
+                }
+                Err(e) => eprintln!("Failed to open webhook event store: {}", e),
+            }
+            Ok(())
+        })
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
             greet,
             make_api_request,
+            upload_media,
+            list_environment_profiles,
+            save_environment_profile,
+            delete_environment_profile,
+            list_webhook_events,
+            delete_webhook_events,
+            replay_webhook_events,
+            run_webhook_generator,
+            stop_webhook_generator,
+            check_webhook_crc,
+            get_ngrok_options,
+            save_ngrok_options,
+            delete_ngrok_options,
+            get_local_certificate,
+            generate_local_certificate,
+            export_local_ca,
             start_ngrok_webhook,
-            get_ngrok_status
+            start_webhook_listener,
+            stop_ngrok_webhook,
+            get_ngrok_status,
+            set_webhook_response_policy,
+            get_webhook_response_policy,
+            list_webhook_tunnels,
+            get_tunnel_log,
+            export_tunnel_log,
+            setup_account_activity,
+            teardown_account_activity,
+            request_activity_replay,
+            list_activity_replays
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
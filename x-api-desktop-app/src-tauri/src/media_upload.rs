@@ -0,0 +1,421 @@
+// Chunked media upload (INIT / APPEND / FINALIZE / STATUS).
+// See https://developer.x.com/en/docs/x-api/v1/media/upload-media/uploading-media/chunked-media-upload
+
+use crate::oauth1::{self, OAuth1Keys};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use ts_rs::TS;
+
+const DEFAULT_UPLOAD_URL: &str = "https://upload.twitter.com/1.1/media/upload.json";
+const DEFAULT_CHUNK_SIZE: usize = 4 * 1024 * 1024; // Well under the 5MB APPEND limit
+const MAX_STATUS_POLLS: u32 = 600;
+
+// Arguments for the `upload_media` command
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaUploadArgs {
+    pub file_path: String,
+    pub media_type: Option<String>, // Guessed from the file extension when omitted
+    pub media_category: Option<String>, // Derived from the media type when omitted
+    pub oauth1_keys: Option<OAuth1Keys>,
+    pub bearer_token: Option<String>, // OAuth2 user-context token, used if no OAuth1 keys
+    pub upload_url: Option<String>,
+    pub chunk_size: Option<usize>,
+}
+
+impl MediaUploadArgs {
+    pub fn upload_url(&self) -> String {
+        self.upload_url.clone().unwrap_or_else(|| DEFAULT_UPLOAD_URL.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Serialize, PartialEq, Debug, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadStage {
+    Init,
+    Append,
+    Finalize,
+    Processing,
+    Done,
+}
+
+// Payload for the `media://upload-progress` event
+#[derive(Clone, Serialize, TS)]
+pub struct MediaUploadProgress {
+    pub file_path: String,
+    pub stage: UploadStage,
+    pub media_id: Option<String>,
+    pub segment_index: Option<u32>,
+    #[ts(type = "number")]
+    pub bytes_sent: u64,
+    #[ts(type = "number")]
+    pub total_bytes: u64,
+    pub processing_percent: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct UploadResponse {
+    media_id_string: String,
+    processing_info: Option<ProcessingInfo>,
+}
+
+#[derive(Deserialize)]
+struct ProcessingInfo {
+    state: String, // pending | in_progress | succeeded | failed
+    check_after_secs: Option<u64>,
+    progress_percent: Option<u8>,
+    error: Option<ProcessingError>,
+}
+
+#[derive(Deserialize)]
+struct ProcessingError {
+    message: Option<String>,
+}
+
+// Works out the media type from the file extension unless one was given
+pub fn detect_media_type(path: &Path, explicit: Option<&str>) -> String {
+    explicit
+        .map(str::to_string)
+        .unwrap_or_else(|| mime_guess::from_path(path).first_or_octet_stream().essence_str().to_string())
+}
+
+// Maps a media type onto the upload category X expects
+pub fn detect_media_category(media_type: &str) -> String {
+    if media_type == "image/gif" {
+        "tweet_gif"
+    } else if media_type.starts_with("video/") {
+        "tweet_video"
+    } else {
+        "tweet_image"
+    }
+    .to_string()
+}
+
+struct Uploader<'a> {
+    client: &'a reqwest::Client,
+    url: String,
+    args: &'a MediaUploadArgs,
+}
+
+impl Uploader<'_> {
+    fn authorize(
+        &self,
+        builder: reqwest::RequestBuilder,
+        method: &str,
+        url: &str,
+        form_params: &[(String, String)],
+    ) -> Result<reqwest::RequestBuilder, String> {
+        if let Some(keys) = &self.args.oauth1_keys {
+            let header = oauth1::authorization_header(method, url, keys, form_params)?;
+            Ok(builder.header(reqwest::header::AUTHORIZATION, header))
+        } else if let Some(token) = &self.args.bearer_token {
+            Ok(builder.bearer_auth(token))
+        } else {
+            Err("Media upload requires OAuth 1.0a keys or a user bearer token".to_string())
+        }
+    }
+
+    async fn send(&self, builder: reqwest::RequestBuilder, command: &str) -> Result<Option<UploadResponse>, String> {
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| format!("{} request failed: {}", command, e))?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read {} response: {}", command, e))?;
+        if !status.is_success() {
+            return Err(format!("{} failed with status {}: {}", command, status.as_u16(), text));
+        }
+        // APPEND answers with an empty body
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|e| format!("Unexpected {} response: {}", command, e))
+    }
+
+    async fn post_form(&self, params: Vec<(String, String)>, command: &str) -> Result<Option<UploadResponse>, String> {
+        let builder = self.client.post(&self.url).form(&params);
+        let builder = self.authorize(builder, "POST", &self.url, &params)?;
+        self.send(builder, command).await
+    }
+
+    async fn append(&self, media_id: &str, segment_index: u32, chunk: Vec<u8>) -> Result<(), String> {
+        let form = reqwest::multipart::Form::new()
+            .text("command", "APPEND")
+            .text("media_id", media_id.to_string())
+            .text("segment_index", segment_index.to_string())
+            .part("media", reqwest::multipart::Part::bytes(chunk).file_name("media"));
+        // Multipart fields are not part of the OAuth1 signature base string
+        let builder = self.client.post(&self.url).multipart(form);
+        let builder = self.authorize(builder, "POST", &self.url, &[])?;
+        self.send(builder, "APPEND").await.map(|_| ())
+    }
+
+    async fn status(&self, media_id: &str) -> Result<Option<UploadResponse>, String> {
+        let mut status_url = url::Url::parse(&self.url).map_err(|e| format!("Invalid upload URL: {}", e))?;
+        status_url
+            .query_pairs_mut()
+            .append_pair("command", "STATUS")
+            .append_pair("media_id", media_id);
+        let builder = self.client.get(status_url.as_str());
+        let builder = self.authorize(builder, "GET", status_url.as_str(), &[])?;
+        self.send(builder, "STATUS").await
+    }
+}
+
+// Reads until `buf` is full or the file ends, returning the number of bytes read
+async fn read_chunk(file: &mut tokio::fs::File, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = file.read(&mut buf[filled..]).await?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+// Runs the full chunked upload for a local file and returns the `media_id_string`
+pub async fn upload(
+    client: &reqwest::Client,
+    args: &MediaUploadArgs,
+    on_progress: impl Fn(MediaUploadProgress),
+) -> Result<String, String> {
+    let path = Path::new(&args.file_path);
+    let total_bytes = tokio::fs::metadata(path)
+        .await
+        .map_err(|e| format!("Failed to read '{}': {}", args.file_path, e))?
+        .len();
+    let media_type = detect_media_type(path, args.media_type.as_deref());
+    let media_category = args
+        .media_category
+        .clone()
+        .unwrap_or_else(|| detect_media_category(&media_type));
+
+    let uploader = Uploader {
+        client,
+        url: args.upload_url(),
+        args,
+    };
+    let progress = |stage, media_id: Option<&str>, segment_index, bytes_sent, processing_percent| {
+        on_progress(MediaUploadProgress {
+            file_path: args.file_path.clone(),
+            stage,
+            media_id: media_id.map(str::to_string),
+            segment_index,
+            bytes_sent,
+            total_bytes,
+            processing_percent,
+        })
+    };
+
+    // --- INIT ---
+    progress(UploadStage::Init, None, None, 0, None);
+    let init = uploader
+        .post_form(
+            vec![
+                ("command".to_string(), "INIT".to_string()),
+                ("total_bytes".to_string(), total_bytes.to_string()),
+                ("media_type".to_string(), media_type),
+                ("media_category".to_string(), media_category),
+            ],
+            "INIT",
+        )
+        .await?
+        .ok_or_else(|| "INIT returned an empty response".to_string())?;
+    let media_id = init.media_id_string;
+
+    // --- APPEND ---
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Failed to open '{}': {}", args.file_path, e))?;
+    let mut buf = vec![0u8; args.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE).max(1)];
+    let mut bytes_sent = 0u64;
+    let mut segment_index = 0u32;
+    loop {
+        let n = read_chunk(&mut file, &mut buf)
+            .await
+            .map_err(|e| format!("Failed to read '{}': {}", args.file_path, e))?;
+        if n == 0 {
+            break;
+        }
+        uploader.append(&media_id, segment_index, buf[..n].to_vec()).await?;
+        bytes_sent += n as u64;
+        progress(UploadStage::Append, Some(&media_id), Some(segment_index), bytes_sent, None);
+        segment_index += 1;
+    }
+
+    // --- FINALIZE ---
+    progress(UploadStage::Finalize, Some(&media_id), None, bytes_sent, None);
+    let mut processing = uploader
+        .post_form(
+            vec![
+                ("command".to_string(), "FINALIZE".to_string()),
+                ("media_id".to_string(), media_id.clone()),
+            ],
+            "FINALIZE",
+        )
+        .await?
+        .and_then(|r| r.processing_info);
+
+    // --- STATUS (only when FINALIZE reports async processing) ---
+    let mut polls = 0;
+    while let Some(info) = processing {
+        match info.state.as_str() {
+            "succeeded" => break,
+            "failed" => {
+                let reason = info
+                    .error
+                    .and_then(|e| e.message)
+                    .unwrap_or_else(|| "unknown error".to_string());
+                return Err(format!("Media processing failed: {}", reason));
+            }
+            _ => {}
+        }
+        polls += 1;
+        if polls > MAX_STATUS_POLLS {
+            return Err(format!("Media {} did not finish processing", media_id));
+        }
+        progress(UploadStage::Processing, Some(&media_id), None, bytes_sent, info.progress_percent);
+        tokio::time::sleep(Duration::from_secs(info.check_after_secs.unwrap_or(1))).await;
+        processing = uploader.status(&media_id).await?.and_then(|r| r.processing_info);
+    }
+
+    progress(UploadStage::Done, Some(&media_id), None, bytes_sent, Some(100));
+    Ok(media_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct MockState {
+        commands: Vec<String>,
+        status_polls: u32,
+    }
+
+    // Minimal stand-in for the upload endpoint. Processing needs one STATUS poll.
+    async fn handle(req: Request<Body>, state: Arc<Mutex<MockState>>) -> Result<Response<Body>, Infallible> {
+        let query = req.uri().query().unwrap_or("").to_string();
+        let content_type = req
+            .headers()
+            .get(hyper::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+        let body_text = String::from_utf8_lossy(&body).to_string();
+
+        let command = if query.contains("command=STATUS") {
+            "STATUS"
+        } else if content_type.starts_with("multipart/form-data") && body_text.contains("APPEND") {
+            "APPEND"
+        } else if body_text.contains("command=INIT") {
+            "INIT"
+        } else if body_text.contains("command=FINALIZE") {
+            "FINALIZE"
+        } else {
+            "UNKNOWN"
+        };
+        let mut state = state.lock().unwrap();
+        state.commands.push(command.to_string());
+
+        let json = match command {
+            "INIT" => r#"{"media_id":42,"media_id_string":"42","expires_after_secs":3600}"#.to_string(),
+            "APPEND" => return Ok(Response::builder().status(204).body(Body::empty()).unwrap()),
+            "FINALIZE" => r#"{"media_id_string":"42","processing_info":{"state":"pending","check_after_secs":0}}"#.to_string(),
+            "STATUS" => {
+                state.status_polls += 1;
+                r#"{"media_id_string":"42","processing_info":{"state":"succeeded","progress_percent":100}}"#.to_string()
+            }
+            _ => return Ok(Response::builder().status(400).body(Body::empty()).unwrap()),
+        };
+        Ok(Response::new(Body::from(json)))
+    }
+
+    async fn start_mock_server() -> (String, Arc<Mutex<MockState>>) {
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let server_state = state.clone();
+        let make_svc = make_service_fn(move |_| {
+            let state = server_state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| handle(req, state.clone()))) }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let url = format!("http://{}/1.1/media/upload.json", server.local_addr());
+        tokio::spawn(server);
+        (url, state)
+    }
+
+    #[test]
+    fn detects_type_and_category() {
+        assert_eq!(detect_media_type(Path::new("clip.mp4"), None), "video/mp4");
+        assert_eq!(detect_media_type(Path::new("clip.bin"), Some("video/quicktime")), "video/quicktime");
+        assert_eq!(detect_media_category("video/mp4"), "tweet_video");
+        assert_eq!(detect_media_category("image/gif"), "tweet_gif");
+        assert_eq!(detect_media_category("image/png"), "tweet_image");
+    }
+
+    #[tokio::test]
+    async fn uploads_in_chunks_and_polls_status() {
+        let (url, state) = start_mock_server().await;
+        let path = std::env::temp_dir().join(format!("media-upload-test-{}.mp4", std::process::id()));
+        tokio::fs::write(&path, vec![7u8; 10]).await.unwrap();
+
+        let args = MediaUploadArgs {
+            file_path: path.to_string_lossy().to_string(),
+            media_type: None,
+            media_category: None,
+            oauth1_keys: None,
+            bearer_token: Some("token".to_string()),
+            upload_url: Some(url),
+            chunk_size: Some(4),
+        };
+        let events = Mutex::new(Vec::new());
+        let media_id = upload(&reqwest::Client::new(), &args, |p| events.lock().unwrap().push(p))
+            .await
+            .unwrap();
+        tokio::fs::remove_file(&path).await.ok();
+
+        assert_eq!(media_id, "42");
+        let state = state.lock().unwrap();
+        assert_eq!(state.commands, ["INIT", "APPEND", "APPEND", "APPEND", "FINALIZE", "STATUS"]);
+        assert_eq!(state.status_polls, 1);
+
+        let events = events.into_inner().unwrap();
+        let appends: Vec<_> = events.iter().filter(|p| p.stage == UploadStage::Append).collect();
+        assert_eq!(appends.len(), 3);
+        assert_eq!(appends.last().unwrap().bytes_sent, 10);
+        assert_eq!(events.last().unwrap().stage, UploadStage::Done);
+    }
+
+    #[tokio::test]
+    async fn requires_credentials() {
+        let (url, _) = start_mock_server().await;
+        let path = std::env::temp_dir().join(format!("media-upload-noauth-{}.png", std::process::id()));
+        tokio::fs::write(&path, vec![1u8; 3]).await.unwrap();
+        let args = MediaUploadArgs {
+            file_path: path.to_string_lossy().to_string(),
+            media_type: None,
+            media_category: None,
+            oauth1_keys: None,
+            bearer_token: None,
+            upload_url: Some(url),
+            chunk_size: None,
+        };
+        let result = upload(&reqwest::Client::new(), &args, |_| {}).await;
+        tokio::fs::remove_file(&path).await.ok();
+        assert!(result.unwrap_err().contains("requires OAuth 1.0a keys"));
+    }
+}
@@ -0,0 +1,166 @@
+// Request body modes for `make_api_request`.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+// How the `body` value from the frontend should be encoded on the wire
+#[derive(Deserialize, Clone, Default)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BodyMode {
+    // `body` is serialized as JSON (the default, used by the v2 endpoints)
+    #[default]
+    Json,
+    // `body` is a flat object sent as application/x-www-form-urlencoded
+    Form,
+    // `body` is a string sent verbatim with the given content type
+    Raw {
+        #[serde(rename = "contentType")]
+        content_type: String,
+    },
+    // The request body is read from a local file; `body` is ignored
+    File {
+        path: String,
+        #[serde(rename = "contentType")]
+        content_type: Option<String>,
+    },
+}
+
+// A body ready to attach to a request
+pub struct PreparedBody {
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+    // Decoded form parameters, needed for the OAuth1 signature base string
+    pub form_params: Vec<(String, String)>,
+}
+
+// Flattens a JSON object into form parameters. Arrays are joined with commas,
+// matching how the v1.1 endpoints accept lists of ids.
+fn form_params(body: &Value) -> Result<Vec<(String, String)>, String> {
+    let object = body
+        .as_object()
+        .ok_or_else(|| "Form body must be a JSON object".to_string())?;
+
+    object
+        .iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| {
+            let encoded = match value {
+                Value::String(s) => s.clone(),
+                Value::Number(_) | Value::Bool(_) => value.to_string(),
+                Value::Array(items) => items
+                    .iter()
+                    .map(|item| match item {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(","),
+                _ => return Err(format!("Form parameter '{}' must not be an object", key)),
+            };
+            Ok((key.clone(), encoded))
+        })
+        .collect()
+}
+
+pub async fn prepare(mode: &BodyMode, body: Option<Value>) -> Result<Option<PreparedBody>, String> {
+    match mode {
+        BodyMode::Json => Ok(body.map(|value| PreparedBody {
+            content_type: "application/json".to_string(),
+            bytes: serde_json::to_vec(&value).unwrap_or_default(),
+            form_params: Vec::new(),
+        })),
+        BodyMode::Form => {
+            let Some(value) = body else { return Ok(None) };
+            let params = form_params(&value)?;
+            let encoded = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(params.iter())
+                .finish();
+            Ok(Some(PreparedBody {
+                content_type: "application/x-www-form-urlencoded".to_string(),
+                bytes: encoded.into_bytes(),
+                form_params: params,
+            }))
+        }
+        BodyMode::Raw { content_type } => {
+            let text = match body {
+                Some(Value::String(s)) => s,
+                Some(other) => other.to_string(),
+                None => String::new(),
+            };
+            Ok(Some(PreparedBody {
+                content_type: content_type.clone(),
+                bytes: text.into_bytes(),
+                form_params: Vec::new(),
+            }))
+        }
+        BodyMode::File { path, content_type } => {
+            let bytes = tokio::fs::read(path)
+                .await
+                .map_err(|e| format!("Failed to read body file '{}': {}", path, e))?;
+            Ok(Some(PreparedBody {
+                content_type: content_type
+                    .clone()
+                    .unwrap_or_else(|| "application/octet-stream".to_string()),
+                bytes,
+                form_params: Vec::new(),
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn serializes_json_bodies() {
+        let body = prepare(&BodyMode::Json, Some(json!({"text": "hi"}))).await.unwrap().unwrap();
+        assert_eq!(body.content_type, "application/json");
+        assert_eq!(body.bytes, br#"{"text":"hi"}"#);
+        assert!(body.form_params.is_empty());
+        assert!(prepare(&BodyMode::Json, None).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn encodes_form_bodies_joining_arrays() {
+        let value = json!({"screen_name": "a b", "user_id": [1, "2", 3], "count": 5, "cursor": null});
+        let body = prepare(&BodyMode::Form, Some(value)).await.unwrap().unwrap();
+        assert_eq!(body.content_type, "application/x-www-form-urlencoded");
+        assert_eq!(String::from_utf8(body.bytes).unwrap(), "count=5&screen_name=a+b&user_id=1%2C2%2C3");
+        assert_eq!(
+            body.form_params,
+            vec![
+                ("count".to_string(), "5".to_string()),
+                ("screen_name".to_string(), "a b".to_string()),
+                ("user_id".to_string(), "1,2,3".to_string()),
+            ]
+        );
+
+        assert!(prepare(&BodyMode::Form, Some(json!(["not", "an", "object"]))).await.is_err());
+        assert!(prepare(&BodyMode::Form, Some(json!({"nested": {"a": 1}}))).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn sends_raw_bodies_with_their_content_type() {
+        let mode = BodyMode::Raw { content_type: "text/plain".to_string() };
+        let body = prepare(&mode, Some(json!("as is"))).await.unwrap().unwrap();
+        assert_eq!((body.content_type.as_str(), body.bytes.as_slice()), ("text/plain", b"as is".as_slice()));
+
+        let body = prepare(&mode, None).await.unwrap().unwrap();
+        assert!(body.bytes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reads_file_bodies() {
+        let path = std::env::temp_dir().join(format!("request-body-{}.bin", std::process::id()));
+        std::fs::write(&path, [0u8, 1, 2]).unwrap();
+        let mode = BodyMode::File { path: path.to_string_lossy().into_owned(), content_type: None };
+        let body = prepare(&mode, Some(json!("ignored"))).await.unwrap().unwrap();
+        assert_eq!((body.content_type.as_str(), body.bytes.as_slice()), ("application/octet-stream", [0u8, 1, 2].as_slice()));
+        std::fs::remove_file(&path).unwrap();
+
+        let Err(error) = prepare(&mode, None).await else { panic!("expected a missing file error") };
+        assert!(error.starts_with("Failed to read body file"), "{}", error);
+    }
+}
@@ -0,0 +1,440 @@
+// Account Activity payloads: typed decoding of webhook deliveries and a display summary.
+// See https://developer.x.com/en/docs/x-api/enterprise/account-activity-api/guides/account-activity-data-objects
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use ts_rs::TS;
+
+// Top-level keys that describe the delivery rather than carry events
+const ENVELOPE_KEYS: &[&str] = &["for_user_id", "user_has_blocked", "users", "apps", "source"];
+
+// --- Payload model ---
+
+#[derive(Deserialize, Clone, Default)]
+pub struct User {
+    // Numeric in Tweet objects, a string in follow/block/mute events
+    #[serde(default)]
+    id: Option<Value>,
+    #[serde(default)]
+    id_str: Option<String>,
+    #[serde(default)]
+    pub screen_name: Option<String>,
+}
+
+impl User {
+    pub fn id(&self) -> Option<String> {
+        self.id_str.clone().or_else(|| self.id.as_ref().and_then(super::as_id))
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Tweet {
+    pub id_str: String,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub full_text: Option<String>,
+    pub user: User,
+    #[serde(default)]
+    pub in_reply_to_user_id_str: Option<String>,
+    #[serde(default)]
+    pub in_reply_to_screen_name: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct FavoriteEvent {
+    pub favorited_status: Tweet,
+    pub user: User,
+}
+
+// follow/unfollow, block/unblock and mute/unmute share this shape
+#[derive(Deserialize, Clone)]
+pub struct RelationshipEvent {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub source: User,
+    pub target: User,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Recipient {
+    pub recipient_id: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct MessageData {
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct MessageCreate {
+    pub target: Recipient,
+    pub sender_id: String,
+    #[serde(default)]
+    pub message_data: Option<MessageData>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct DirectMessageEvent {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub id: String,
+    #[serde(default)]
+    pub message_create: Option<MessageCreate>,
+}
+
+// Typing indicators and read receipts
+#[derive(Deserialize, Clone)]
+pub struct DirectMessageIndicator {
+    pub sender_id: String,
+    pub target: Recipient,
+    #[serde(default)]
+    pub last_read_event_id: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct DeletedStatus {
+    pub id: String,
+    pub user_id: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct TweetDeleteEvent {
+    pub status: DeletedStatus,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct RevokeSource {
+    pub user_id: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct RevokeTarget {
+    pub app_id: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Revoke {
+    pub source: RevokeSource,
+    pub target: RevokeTarget,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct UserEvent {
+    pub revoke: Revoke,
+}
+
+#[derive(Clone)]
+pub enum ActivityEvent {
+    TweetCreate(Tweet),
+    Favorite(FavoriteEvent),
+    Follow(RelationshipEvent),
+    Block(RelationshipEvent),
+    Mute(RelationshipEvent),
+    DirectMessage(DirectMessageEvent),
+    DirectMessageTyping(DirectMessageIndicator),
+    DirectMessageRead(DirectMessageIndicator),
+    TweetDelete(TweetDeleteEvent),
+    UserRevoke(UserEvent),
+}
+
+fn decode_list<T: for<'de> Deserialize<'de>>(value: Value, wrap: fn(T) -> ActivityEvent) -> Result<Vec<ActivityEvent>, serde_json::Error> {
+    Ok(serde_json::from_value::<Vec<T>>(value)?.into_iter().map(wrap).collect())
+}
+
+impl ActivityEvent {
+    // Decodes the events under one top-level key; None if the key isn't an event key we know
+    fn decode(key: &str, value: Value) -> Option<Result<Vec<ActivityEvent>, serde_json::Error>> {
+        let events = match key {
+            "tweet_create_events" => decode_list(value, ActivityEvent::TweetCreate),
+            "favorite_events" => decode_list(value, ActivityEvent::Favorite),
+            "follow_events" => decode_list(value, ActivityEvent::Follow),
+            "block_events" => decode_list(value, ActivityEvent::Block),
+            "mute_events" => decode_list(value, ActivityEvent::Mute),
+            "direct_message_events" => decode_list(value, ActivityEvent::DirectMessage),
+            "direct_message_indicate_typing_events" => decode_list(value, ActivityEvent::DirectMessageTyping),
+            "direct_message_mark_read_events" => decode_list(value, ActivityEvent::DirectMessageRead),
+            "tweet_delete_events" => decode_list(value, ActivityEvent::TweetDelete),
+            "user_event" => serde_json::from_value(value).map(|event| vec![ActivityEvent::UserRevoke(event)]),
+            _ => return None,
+        };
+        Some(events)
+    }
+
+    // The payload key this event arrived under
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            ActivityEvent::TweetCreate(_) => "tweet_create_events",
+            ActivityEvent::Favorite(_) => "favorite_events",
+            ActivityEvent::Follow(_) => "follow_events",
+            ActivityEvent::Block(_) => "block_events",
+            ActivityEvent::Mute(_) => "mute_events",
+            ActivityEvent::DirectMessage(_) => "direct_message_events",
+            ActivityEvent::DirectMessageTyping(_) => "direct_message_indicate_typing_events",
+            ActivityEvent::DirectMessageRead(_) => "direct_message_mark_read_events",
+            ActivityEvent::TweetDelete(_) => "tweet_delete_events",
+            ActivityEvent::UserRevoke(_) => "user_event",
+        }
+    }
+}
+
+pub struct ActivityPayload {
+    pub for_user_id: Option<String>,
+    pub user_has_blocked: Option<bool>,
+    pub events: Vec<ActivityEvent>,
+    // Every event-carrying key in the payload, decoded or not
+    pub event_keys: Vec<String>,
+    // Users referenced by id in DM events, keyed by id
+    pub users: HashMap<String, User>,
+    // Keys we don't model yet, kept so new event types are visible instead of silently dropped
+    pub unknown_keys: Vec<String>,
+    // Known keys whose contents didn't match the expected shape
+    pub errors: Vec<String>,
+}
+
+impl ActivityPayload {
+    pub fn decode(body: &[u8]) -> Result<Self, String> {
+        let payload: Map<String, Value> = serde_json::from_slice(body)
+            .map_err(|e| format!("Not an Account Activity payload: {}", e))?;
+
+        let mut decoded = ActivityPayload {
+            for_user_id: payload.get("for_user_id").and_then(Value::as_str).map(str::to_string),
+            user_has_blocked: payload.get("user_has_blocked").and_then(Value::as_bool),
+            events: Vec::new(),
+            event_keys: Vec::new(),
+            users: payload
+                .get("users")
+                .cloned()
+                .and_then(|users| serde_json::from_value(users).ok())
+                .unwrap_or_default(),
+            unknown_keys: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        for (key, value) in payload {
+            if ENVELOPE_KEYS.contains(&key.as_str()) {
+                continue;
+            }
+            decoded.event_keys.push(key.clone());
+            match ActivityEvent::decode(&key, value) {
+                Some(Ok(events)) => decoded.events.extend(events),
+                Some(Err(e)) => decoded.errors.push(format!("{}: {}", key, e)),
+                None => decoded.unknown_keys.push(key),
+            }
+        }
+        Ok(decoded)
+    }
+
+    fn user(&self, id: &str) -> ActivityUser {
+        ActivityUser {
+            id: id.to_string(),
+            screen_name: self.users.get(id).and_then(|u| u.screen_name.clone()),
+        }
+    }
+
+    fn summarize_event(&self, event: &ActivityEvent) -> ActivityEventSummary {
+        let mut summary = ActivityEventSummary {
+            event_type: event.event_type().to_string(),
+            id: None,
+            action: None,
+            actor: None,
+            target: None,
+            text: None,
+        };
+        match event {
+            ActivityEvent::TweetCreate(tweet) => {
+                summary.id = Some(tweet.id_str.clone());
+                summary.action = Some("tweet".to_string());
+                summary.actor = ActivityUser::from_user(&tweet.user);
+                summary.target = tweet.in_reply_to_user_id_str.as_ref().map(|id| ActivityUser {
+                    id: id.clone(),
+                    screen_name: tweet.in_reply_to_screen_name.clone(),
+                });
+                summary.text = tweet.full_text.clone().or_else(|| tweet.text.clone());
+            }
+            ActivityEvent::Favorite(favorite) => {
+                summary.id = Some(favorite.favorited_status.id_str.clone());
+                summary.action = Some("favorite".to_string());
+                summary.actor = ActivityUser::from_user(&favorite.user);
+                summary.target = ActivityUser::from_user(&favorite.favorited_status.user);
+                summary.text = favorite.favorited_status.text.clone();
+            }
+            ActivityEvent::Follow(relationship) | ActivityEvent::Block(relationship) | ActivityEvent::Mute(relationship) => {
+                summary.action = Some(relationship.kind.clone());
+                summary.actor = ActivityUser::from_user(&relationship.source);
+                summary.target = ActivityUser::from_user(&relationship.target);
+            }
+            ActivityEvent::DirectMessage(message) => {
+                summary.id = Some(message.id.clone());
+                summary.action = Some(message.kind.clone());
+                if let Some(create) = &message.message_create {
+                    summary.actor = Some(self.user(&create.sender_id));
+                    summary.target = Some(self.user(&create.target.recipient_id));
+                    summary.text = create.message_data.as_ref().and_then(|data| data.text.clone());
+                }
+            }
+            ActivityEvent::DirectMessageTyping(indicator) | ActivityEvent::DirectMessageRead(indicator) => {
+                summary.action = Some(if matches!(event, ActivityEvent::DirectMessageTyping(_)) { "typing" } else { "read" }.to_string());
+                summary.actor = Some(self.user(&indicator.sender_id));
+                summary.target = Some(self.user(&indicator.target.recipient_id));
+                summary.text = indicator.last_read_event_id.as_ref().map(|id| format!("Read up to message {}", id));
+            }
+            ActivityEvent::TweetDelete(delete) => {
+                summary.id = Some(delete.status.id.clone());
+                summary.action = Some("delete".to_string());
+                summary.actor = Some(self.user(&delete.status.user_id));
+                summary.text = Some(format!("Deleted Tweet {}", delete.status.id));
+            }
+            ActivityEvent::UserRevoke(user_event) => {
+                summary.action = Some("revoke".to_string());
+                summary.actor = Some(self.user(&user_event.revoke.source.user_id));
+                summary.text = Some(format!("Revoked access for app {}", user_event.revoke.target.app_id));
+            }
+        }
+        summary
+    }
+
+    pub fn summary(&self) -> ActivitySummary {
+        ActivitySummary {
+            for_user_id: self.for_user_id.clone(),
+            user_has_blocked: self.user_has_blocked,
+            event_type: self
+                .events
+                .first()
+                .map(|event| event.event_type().to_string())
+                .or_else(|| self.event_keys.first().cloned()),
+            events: self.events.iter().map(|event| self.summarize_event(event)).collect(),
+            unknown_keys: self.unknown_keys.clone(),
+            errors: self.errors.clone(),
+        }
+    }
+}
+
+// --- Summary sent to the frontend ---
+
+#[derive(Serialize, Clone, TS)]
+pub struct ActivityUser {
+    pub id: String,
+    pub screen_name: Option<String>,
+}
+
+impl ActivityUser {
+    fn from_user(user: &User) -> Option<Self> {
+        Some(ActivityUser { id: user.id()?, screen_name: user.screen_name.clone() })
+    }
+}
+
+#[derive(Serialize, Clone, TS)]
+pub struct ActivityEventSummary {
+    pub event_type: String, // Payload key, e.g. "follow_events"
+    pub id: Option<String>, // Tweet or message the event is about
+    pub action: Option<String>, // e.g. "follow", "unblock", "message_create"
+    pub actor: Option<ActivityUser>, // Who did it
+    pub target: Option<ActivityUser>, // Who it was done to, if anyone
+    pub text: Option<String>,
+}
+
+#[derive(Serialize, Clone, TS)]
+pub struct ActivitySummary {
+    pub for_user_id: Option<String>,
+    pub user_has_blocked: Option<bool>, // Set on Tweet events from users the subscriber blocked
+    pub event_type: Option<String>, // Type of the first event (or event key), used for filtering
+    pub events: Vec<ActivityEventSummary>,
+    pub unknown_keys: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+// Summary of a delivery body, or None if it isn't an Account Activity payload
+pub fn summarize(body: &[u8]) -> Option<ActivitySummary> {
+    ActivityPayload::decode(body).ok().map(|payload| payload.summary())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(body: &str) -> ActivitySummary {
+        summarize(body.as_bytes()).expect("payload decodes")
+    }
+
+    fn who(user: &Option<ActivityUser>) -> Option<(&str, Option<&str>)> {
+        user.as_ref().map(|u| (u.id.as_str(), u.screen_name.as_deref()))
+    }
+
+    #[test]
+    fn decodes_tweets_and_favorites() {
+        let summary = decode(r#"{
+            "for_user_id": "2244994945",
+            "tweet_create_events": [{
+                "id_str": "10", "text": "@XDevelopers hi",
+                "user": {"id": 6253282, "id_str": "6253282", "screen_name": "api"},
+                "in_reply_to_user_id_str": "2244994945", "in_reply_to_screen_name": "XDevelopers"
+            }]
+        }"#);
+        assert_eq!(summary.for_user_id.as_deref(), Some("2244994945"));
+        assert_eq!(summary.event_type.as_deref(), Some("tweet_create_events"));
+        let event = &summary.events[0];
+        assert_eq!(who(&event.actor), Some(("6253282", Some("api"))));
+        assert_eq!(who(&event.target), Some(("2244994945", Some("XDevelopers"))));
+        assert_eq!(event.id.as_deref(), Some("10"));
+        assert_eq!(event.text.as_deref(), Some("@XDevelopers hi"));
+
+        let summary = decode(r#"{
+            "for_user_id": "2244994945",
+            "favorite_events": [{
+                "id": "a", "favorited_status": {"id_str": "11", "text": "liked", "user": {"id": 2244994945, "screen_name": "XDevelopers"}},
+                "user": {"id": 6253282, "screen_name": "api"}
+            }]
+        }"#);
+        let event = &summary.events[0];
+        assert_eq!(event.action.as_deref(), Some("favorite"));
+        assert_eq!(who(&event.actor), Some(("6253282", Some("api"))));
+        assert_eq!(who(&event.target), Some(("2244994945", Some("XDevelopers"))));
+    }
+
+    #[test]
+    fn decodes_relationships_and_direct_messages() {
+        let summary = decode(r#"{
+            "for_user_id": "1",
+            "follow_events": [{"type": "unfollow", "source": {"id": "1", "screen_name": "a"}, "target": {"id": "2", "screen_name": "b"}}],
+            "block_events": [{"type": "block", "source": {"id": "1"}, "target": {"id": "3"}}],
+            "direct_message_events": [{"type": "message_create", "id": "9", "message_create": {
+                "target": {"recipient_id": "1"}, "sender_id": "2", "message_data": {"text": "hello"}}}],
+            "users": {"2": {"id": "2", "screen_name": "b"}}
+        }"#);
+        let mut actions: Vec<_> = summary.events.iter().map(|e| (e.event_type.as_str(), e.action.as_deref())).collect();
+        actions.sort();
+        assert_eq!(actions, vec![
+            ("block_events", Some("block")),
+            ("direct_message_events", Some("message_create")),
+            ("follow_events", Some("unfollow")),
+        ]);
+        let message = summary.events.iter().find(|e| e.event_type == "direct_message_events").unwrap();
+        assert_eq!(who(&message.actor), Some(("2", Some("b"))));
+        assert_eq!(who(&message.target), Some(("1", None)));
+        assert_eq!(message.text.as_deref(), Some("hello"));
+        assert!(summary.unknown_keys.is_empty());
+    }
+
+    #[test]
+    fn reports_unknown_keys_and_malformed_events() {
+        let summary = decode(r#"{
+            "for_user_id": "1",
+            "tweet_delete_events": [{"status": {"id": "5", "user_id": "1"}, "timestamp_ms": "1"}],
+            "user_event": {"revoke": {"date_time": "x", "target": {"app_id": "7"}, "source": {"user_id": "1"}}},
+            "shiny_new_events": [{}],
+            "mute_events": [{"type": "mute"}]
+        }"#);
+        assert_eq!(summary.events.len(), 2);
+        let revoke = summary.events.iter().find(|e| e.event_type == "user_event").unwrap();
+        assert_eq!(revoke.text.as_deref(), Some("Revoked access for app 7"));
+        assert_eq!(summary.unknown_keys, vec!["shiny_new_events".to_string()]);
+        assert_eq!(summary.errors.len(), 1);
+        assert!(summary.errors[0].starts_with("mute_events:"));
+
+        assert!(summarize(b"not json").is_none());
+        assert!(summarize(b"[1, 2]").is_none());
+    }
+}
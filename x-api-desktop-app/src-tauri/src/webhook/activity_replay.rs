@@ -0,0 +1,206 @@
+// Account Activity Replay: asks X to redeliver a webhook's events from a past time window (e.g.
+// to backfill after our consumer was down), tracks the job and tags the deliveries it produces.
+// See https://docs.x.com/x-api/account-activity/introduction
+
+use super::as_id;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayJobState {
+    Running, // Accepted by X; redelivered events are arriving
+    Complete,
+    Failed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayJob {
+    pub job_id: String,
+    pub tunnel_id: String, // The tunnel whose deliveries are tagged with this job
+    pub webhook_id: String,
+    pub from: u64, // Window start, ms since the Unix epoch
+    pub to: u64, // Window end (exclusive), ms since the Unix epoch
+    pub state: ReplayJobState,
+    pub description: Option<String>, // From X's replay_job_status, once it arrives
+    pub requested_at: u64,
+    pub finished_at: Option<u64>,
+    pub replayed: u32, // Deliveries tagged with this job so far
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+impl ReplayJob {
+    // A job X just accepted
+    pub fn started(job_id: String, tunnel_id: String, webhook_id: String, from: u64, to: u64) -> Self {
+        ReplayJob {
+            job_id,
+            tunnel_id,
+            webhook_id,
+            from,
+            to,
+            state: ReplayJobState::Running,
+            description: None,
+            requested_at: now_millis(),
+            finished_at: None,
+            replayed: 0,
+        }
+    }
+}
+
+// `YYYYMMDDHHmm` in UTC, the format X takes for from_date and to_date
+pub fn format_replay_date(ms: u64) -> String {
+    let format = format_description!("[year][month][day][hour][minute]");
+    i64::try_from(ms / 1000)
+        .ok()
+        .and_then(|secs| OffsetDateTime::from_unix_timestamp(secs).ok())
+        .and_then(|at| at.format(format).ok())
+        .unwrap_or_default()
+}
+
+pub fn validate_window(from: u64, to: u64) -> Result<(), String> {
+    if from >= to {
+        return Err("The replay window must start before it ends".to_string());
+    }
+    if to > now_millis() {
+        return Err("The replay window can't end in the future".to_string());
+    }
+    Ok(())
+}
+
+fn as_millis(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(ms) => ms.parse().ok(),
+        Value::Number(ms) => ms.as_u64(),
+        _ => None,
+    }
+}
+
+// When the first event in a delivery happened, from its timestamp_ms or created_timestamp
+fn event_timestamp(payload: &Value) -> Option<u64> {
+    let object = payload.as_object()?;
+    object.iter().filter(|(key, _)| key.ends_with("_events")).find_map(|(_, events)| {
+        let event = events.as_array()?.first()?;
+        event.get("timestamp_ms").or_else(|| event.get("created_timestamp")).and_then(as_millis)
+    })
+}
+
+// Replay jobs of every tunnel, shared with the webhook listeners
+#[derive(Default)]
+pub struct ReplayJobs {
+    jobs: Mutex<Vec<ReplayJob>>,
+}
+
+impl ReplayJobs {
+    pub fn add(&self, job: ReplayJob) -> Result<(), String> {
+        self.jobs.lock().map_err(|e| format!("Mutex lock error: {}", e))?.push(job);
+        Ok(())
+    }
+
+    // Newest first
+    pub fn list(&self, tunnel_id: Option<&str>) -> Result<Vec<ReplayJob>, String> {
+        let guard = self.jobs.lock().map_err(|e| format!("Mutex lock error: {}", e))?;
+        Ok(guard.iter().rev().filter(|job| tunnel_id.is_none_or(|id| job.tunnel_id == id)).cloned().collect())
+    }
+
+    // The replay job a delivery belongs to, if any. X reports a job's end with a
+    // `replay_job_status` delivery; other deliveries count as replayed when a running job for the
+    // tunnel covers their event time, since live events are always newer than the window.
+    pub fn classify(&self, tunnel_id: &str, body: &[u8]) -> Option<String> {
+        let payload: Value = serde_json::from_slice(body).ok()?;
+        let mut jobs = self.jobs.lock().ok()?;
+
+        if let Some(status) = payload.get("replay_job_status") {
+            let job_id = status.get("job_id").and_then(as_id)?;
+            let job = jobs.iter_mut().find(|job| job.job_id == job_id)?;
+            let state = status.get("job_state").and_then(Value::as_str).unwrap_or_default();
+            job.state = if state.eq_ignore_ascii_case("complete") { ReplayJobState::Complete } else { ReplayJobState::Failed };
+            job.description = status.get("job_state_description").and_then(Value::as_str).map(str::to_string);
+            job.finished_at = Some(now_millis());
+            return Some(job_id);
+        }
+
+        let at = event_timestamp(&payload)?;
+        let job = jobs.iter_mut().rev().find(|job| {
+            job.tunnel_id == tunnel_id && job.state == ReplayJobState::Running && (job.from..job.to).contains(&at)
+        })?;
+        job.replayed += 1;
+        Some(job.job_id.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webhook::setup::XApi;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use std::collections::HashMap;
+    use std::convert::Infallible;
+    use std::sync::Arc;
+
+    // 2024-02-29 13:05 UTC
+    const LEAP_DAY: u64 = 1_709_211_900_000;
+
+    #[test]
+    fn formats_dates_for_x() {
+        assert_eq!(format_replay_date(0), "197001010000");
+        assert_eq!(format_replay_date(LEAP_DAY), "202402291305");
+        assert_eq!(format_replay_date(LEAP_DAY + 59_999), "202402291305");
+    }
+
+    #[tokio::test]
+    async fn requests_a_replay_and_tags_its_deliveries() {
+        // Stand-in for the X API that accepts one replay request and records its URI
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let server_requested = requested.clone();
+        let make_svc = make_service_fn(move |_| {
+            let requested = server_requested.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    requested.lock().unwrap().push(format!("{} {}", req.method(), req.uri()));
+                    async { Ok::<_, Infallible>(Response::new(Body::from(r#"{"data":{"job_id":"1788","created_at":"x"}}"#))) }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let api = XApi {
+            client: reqwest::Client::new(),
+            base_url: format!("http://{}", server.local_addr()),
+            headers: HashMap::new(),
+            bearer_token: "app-token".to_string(),
+            oauth1_keys: None,
+        };
+        tokio::spawn(server);
+
+        let (from, to) = (LEAP_DAY, LEAP_DAY + 3_600_000);
+        let job_id = api.request_replay("77", from, to).await.unwrap();
+        assert_eq!(job_id, "1788");
+        assert_eq!(
+            *requested.lock().unwrap(),
+            ["POST /2/account_activity/replay/webhooks/77/subscriptions/all?from_date=202402291305&to_date=202402291405"]
+        );
+
+        let jobs = ReplayJobs::default();
+        jobs.add(ReplayJob::started(job_id, "prod".to_string(), "77".to_string(), from, to)).unwrap();
+
+        let delivery = |at: u64| format!(r#"{{"for_user_id":"1","favorite_events":[{{"id":"f","timestamp_ms":"{}"}}]}}"#, at);
+        assert_eq!(jobs.classify("prod", delivery(from + 1000).as_bytes()).as_deref(), Some("1788"));
+        assert_eq!(jobs.classify("staging", delivery(from + 1000).as_bytes()), None);
+        assert_eq!(jobs.classify("prod", delivery(now_millis()).as_bytes()), None);
+
+        let status = r#"{"replay_job_status":{"webhook_id":"77","job_id":"1788","job_state":"Complete","job_state_description":"done"}}"#;
+        assert_eq!(jobs.classify("prod", status.as_bytes()).as_deref(), Some("1788"));
+        let job = &jobs.list(Some("prod")).unwrap()[0];
+        assert_eq!((job.state, job.replayed, job.description.as_deref()), (ReplayJobState::Complete, 1, Some("done")));
+        // Finished jobs no longer claim deliveries
+        assert_eq!(jobs.classify("prod", delivery(from + 2000).as_bytes()), None);
+    }
+}
@@ -0,0 +1,104 @@
+// Issues a CRC challenge against a webhook server, the way X does on registration and
+// hourly re-validation, and reports everything that would make it fail.
+
+use super::service;
+use rand::Rng;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+// X gives the server this long to answer
+const CRC_DEADLINE: Duration = Duration::from_secs(3);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize)]
+pub struct CrcCheckReport {
+    pub url: String, // Including the crc_token query parameter
+    pub crc_token: String,
+    pub expected_token: String,
+    pub response_token: Option<String>,
+    pub status: Option<u16>,
+    pub content_type: Option<String>,
+    pub latency_ms: u64,
+    pub valid: bool, // The server would pass X's CRC check
+    pub problems: Vec<String>,
+}
+
+fn random_token() -> String {
+    let mut rng = rand::thread_rng();
+    (0..32).map(|_| format!("{:x}", rng.gen_range(0..16u8))).collect()
+}
+
+pub async fn check(client: &reqwest::Client, webhook_url: &str, consumer_secret: &str) -> Result<CrcCheckReport, String> {
+    let crc_token = random_token();
+    let mut url = url::Url::parse(webhook_url).map_err(|e| format!("Invalid webhook URL: {}", e))?;
+    url.query_pairs_mut().append_pair("crc_token", &crc_token);
+
+    let mut report = CrcCheckReport {
+        url: url.to_string(),
+        expected_token: service::crc_response_token(consumer_secret, &crc_token),
+        crc_token,
+        response_token: None,
+        status: None,
+        content_type: None,
+        latency_ms: 0,
+        valid: false,
+        problems: Vec::new(),
+    };
+    if url.scheme() != "https" {
+        report.problems.push("X only registers https:// webhook URLs".to_string());
+    }
+
+    let started = Instant::now();
+    let response = client.get(url).timeout(REQUEST_TIMEOUT).send().await;
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            report.latency_ms = started.elapsed().as_millis() as u64;
+            report.problems.push(format!("Request failed: {}", e));
+            return Ok(report);
+        }
+    };
+    report.status = Some(response.status().as_u16());
+    report.content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = response.text().await;
+    let elapsed = started.elapsed();
+    report.latency_ms = elapsed.as_millis() as u64;
+
+    if elapsed > CRC_DEADLINE {
+        report.problems.push(format!("Took {} ms; X requires a response within {} s", report.latency_ms, CRC_DEADLINE.as_secs()));
+    }
+    if report.status != Some(200) {
+        report.problems.push(format!("Expected HTTP 200, got {}", report.status.unwrap_or_default()));
+    }
+    if !report.content_type.as_deref().is_some_and(|ct| ct.starts_with("application/json")) {
+        report.problems.push(format!(
+            "Expected Content-Type application/json, got {}",
+            report.content_type.as_deref().unwrap_or("none")
+        ));
+    }
+
+    match body.map_err(|e| e.to_string()).and_then(|body| serde_json::from_str::<serde_json::Value>(&body).map_err(|e| e.to_string())) {
+        Ok(json) => match json.get("response_token").and_then(|v| v.as_str()) {
+            Some(token) => {
+                report.response_token = Some(token.to_string());
+                if token != report.expected_token {
+                    report.problems.push(
+                        "response_token does not match; check the consumer secret and that the token is sha256=<base64 HMAC-SHA256>".to_string(),
+                    );
+                }
+            }
+            None => report.problems.push("Response JSON has no response_token string".to_string()),
+        },
+        Err(e) => report.problems.push(format!("Response body is not JSON: {}", e)),
+    }
+
+    // The scheme is reported but not held against the server, so local servers can be checked
+    report.valid = report.status == Some(200)
+        && elapsed <= CRC_DEADLINE
+        && report.response_token.as_deref() == Some(report.expected_token.as_str());
+    Ok(report)
+}
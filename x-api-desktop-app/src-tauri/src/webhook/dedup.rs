@@ -0,0 +1,165 @@
+// Duplicate delivery detection: X can deliver the same event more than once, so each delivery
+// gets a fingerprint and repeats within a window are flagged and counted per tunnel.
+
+use super::as_id;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// How long a delivery counts as a repeat of an earlier one unless the tunnel sets its own
+pub const DEFAULT_DEDUP_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+fn hex_sha256(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Identifies the event(s) a delivery carries. Account Activity payloads are keyed by the
+// subscribed user and the ids of their events, so a redelivery matches even if X serializes it
+// differently; anything else falls back to a hash of the raw body.
+pub fn fingerprint(body: &[u8]) -> String {
+    let payload: Option<Value> = serde_json::from_slice(body).ok();
+    let ids = payload.as_ref().and_then(Value::as_object).and_then(|object| {
+        let mut ids: Vec<String> = object
+            .iter()
+            .filter(|(key, _)| key.ends_with("_events"))
+            .flat_map(|(key, events)| {
+                let events = events.as_array().map(Vec::as_slice).unwrap_or_default();
+                events.iter().filter_map(move |event| {
+                    let id = event.get("id_str").or_else(|| event.get("id")).and_then(as_id)?;
+                    Some(format!("{}:{}", key, id))
+                })
+            })
+            .collect();
+        if ids.is_empty() {
+            return None;
+        }
+        ids.sort();
+        let for_user_id = object.get("for_user_id").and_then(as_id).unwrap_or_default();
+        Some(format!("{}|{}", for_user_id, ids.join(",")))
+    });
+    match ids {
+        Some(ids) => format!("ids:{}", hex_sha256(ids.as_bytes())),
+        None => format!("body:{}", hex_sha256(body)),
+    }
+}
+
+struct Seen {
+    window: Duration,
+    first_seen: HashMap<String, Instant>,
+    order: VecDeque<(Instant, String)>, // Oldest first, for expiring `first_seen`
+    deliveries: u64,
+    duplicates: u64,
+}
+
+impl Seen {
+    fn expire(&mut self, now: Instant) {
+        while let Some((at, _)) = self.order.front() {
+            if now.duration_since(*at) < self.window {
+                break;
+            }
+            if let Some((at, fingerprint)) = self.order.pop_front() {
+                if self.first_seen.get(&fingerprint) == Some(&at) {
+                    self.first_seen.remove(&fingerprint);
+                }
+            }
+        }
+    }
+}
+
+// Delivery counts for one tunnel, for measuring how often X redelivers
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct DeliveryCounts {
+    pub deliveries: u64,
+    pub duplicates: u64,
+}
+
+// Fingerprints seen recently on one tunnel, shared by its listener and its status
+#[derive(Clone)]
+pub struct DuplicateTracker(Arc<Mutex<Seen>>);
+
+impl Default for DuplicateTracker {
+    fn default() -> Self {
+        DuplicateTracker::new(DEFAULT_DEDUP_WINDOW)
+    }
+}
+
+impl DuplicateTracker {
+    // A zero window still counts deliveries but never flags a repeat
+    pub fn new(window: Duration) -> Self {
+        DuplicateTracker(Arc::new(Mutex::new(Seen {
+            window,
+            first_seen: HashMap::new(),
+            order: VecDeque::new(),
+            deliveries: 0,
+            duplicates: 0,
+        })))
+    }
+
+    // Records a delivery; true if the same fingerprint was first seen within the window
+    pub fn record(&self, fingerprint: &str) -> bool {
+        self.record_at(fingerprint, Instant::now())
+    }
+
+    fn record_at(&self, fingerprint: &str, now: Instant) -> bool {
+        let Ok(mut seen) = self.0.lock() else { return false };
+        seen.expire(now);
+        seen.deliveries += 1;
+        if seen.first_seen.contains_key(fingerprint) {
+            seen.duplicates += 1;
+            return true;
+        }
+        if !seen.window.is_zero() {
+            seen.first_seen.insert(fingerprint.to_string(), now);
+            seen.order.push_back((now, fingerprint.to_string()));
+        }
+        false
+    }
+
+    pub fn counts(&self) -> DeliveryCounts {
+        self.0
+            .lock()
+            .map(|seen| DeliveryCounts { deliveries: seen.deliveries, duplicates: seen.duplicates })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprints_by_event_ids() {
+        let delivery = r#"{"for_user_id":"1","favorite_events":[{"id":"f1","timestamp_ms":"1"}]}"#;
+        let reserialized = r#"{ "favorite_events": [ { "timestamp_ms": "1", "id": "f1" } ], "for_user_id": "1" }"#;
+        assert_eq!(fingerprint(delivery.as_bytes()), fingerprint(reserialized.as_bytes()));
+        assert!(fingerprint(delivery.as_bytes()).starts_with("ids:"));
+
+        let other_user = r#"{"for_user_id":"2","favorite_events":[{"id":"f1","timestamp_ms":"1"}]}"#;
+        assert_ne!(fingerprint(delivery.as_bytes()), fingerprint(other_user.as_bytes()));
+
+        // Without event ids, only identical bodies match
+        assert_eq!(fingerprint(b"{}"), fingerprint(b"{}"));
+        assert_ne!(fingerprint(b"{}"), fingerprint(b"{ }"));
+        assert!(fingerprint(b"not json").starts_with("body:"));
+    }
+
+    #[test]
+    fn flags_repeats_within_the_window() {
+        let tracker = DuplicateTracker::new(Duration::from_secs(60));
+        let start = Instant::now();
+        assert!(!tracker.record_at("a", start));
+        assert!(tracker.record_at("a", start + Duration::from_secs(30)));
+        assert!(!tracker.record_at("b", start + Duration::from_secs(30)));
+        // The window runs from the first delivery, so this one starts over
+        assert!(!tracker.record_at("a", start + Duration::from_secs(61)));
+        assert!(tracker.record_at("a", start + Duration::from_secs(62)));
+        assert_eq!(tracker.counts(), DeliveryCounts { deliveries: 5, duplicates: 2 });
+
+        let disabled = DuplicateTracker::new(Duration::ZERO);
+        assert!(!disabled.record("a"));
+        assert!(!disabled.record("a"));
+        assert_eq!(disabled.counts(), DeliveryCounts { deliveries: 2, duplicates: 0 });
+    }
+}
@@ -0,0 +1,137 @@
+// Relays webhook requests to a local development server and records what it answered.
+
+use hyper::{Body, HeaderMap, Method, Response, StatusCode, Uri};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use ts_rs::TS;
+
+// Downstream bodies longer than this are cut off in the recorded result
+const RECORDED_BODY_LIMIT: usize = 16 * 1024;
+
+// Not meaningful across the hop; reqwest sets its own
+const HOP_HEADERS: &[&str] = &["host", "connection", "content-length", "transfer-encoding", "keep-alive", "upgrade"];
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardConfig {
+    pub url: String, // e.g. http://localhost:3000/webhook
+    // Answer X with the downstream response instead of the receiver's own
+    #[serde(default)]
+    pub return_downstream: bool,
+}
+
+// What the downstream server did with a forwarded request
+#[derive(Serialize, Deserialize, Clone, TS)]
+pub struct ForwardResult {
+    pub url: String,
+    pub status: Option<u16>, // None if the request failed
+    #[ts(type = "number")]
+    pub latency_ms: u64,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>, // Lossy UTF-8, truncated
+    pub error: Option<String>,
+}
+
+// The downstream response in full, for passing back to X
+pub struct Forwarded {
+    pub result: ForwardResult,
+    response: Option<(StatusCode, HeaderMap, hyper::body::Bytes)>,
+}
+
+impl Forwarded {
+    // Downstream response as a hyper response, or 502 if forwarding failed
+    pub fn into_response(self) -> Response<Body> {
+        let Some((status, headers, body)) = self.response else {
+            let message = self.result.error.unwrap_or_default();
+            let mut resp = Response::new(Body::from(format!("Forwarding failed: {}", message)));
+            *resp.status_mut() = StatusCode::BAD_GATEWAY;
+            return resp;
+        };
+        let mut resp = Response::new(Body::from(body));
+        *resp.status_mut() = status;
+        for (name, value) in headers.iter() {
+            if !HOP_HEADERS.contains(&name.as_str()) {
+                resp.headers_mut().append(name.clone(), value.clone());
+            }
+        }
+        resp
+    }
+}
+
+// Downstream URL for a request: the configured URL with the incoming query appended
+fn target_url(config: &ForwardConfig, uri: &Uri) -> Result<url::Url, String> {
+    let mut url = url::Url::parse(&config.url).map_err(|e| format!("Invalid forward URL: {}", e))?;
+    if let Some(query) = uri.query() {
+        url.query_pairs_mut().extend_pairs(url::form_urlencoded::parse(query.as_bytes()));
+    }
+    Ok(url)
+}
+
+pub async fn forward(
+    client: &reqwest::Client,
+    config: &ForwardConfig,
+    method: &Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+    body: hyper::body::Bytes,
+) -> Forwarded {
+    let started = Instant::now();
+    let mut result = ForwardResult {
+        url: config.url.clone(),
+        status: None,
+        latency_ms: 0,
+        headers: Vec::new(),
+        body: None,
+        error: None,
+    };
+
+    let outcome = async {
+        let url = target_url(config, uri)?;
+        result.url = url.to_string();
+        let method = reqwest::Method::from_bytes(method.as_str().as_bytes()).map_err(|e| e.to_string())?;
+        let mut request = client.request(method, url).body(body);
+        for (name, value) in headers.iter() {
+            if !HOP_HEADERS.contains(&name.as_str()) {
+                request = request.header(name.as_str(), value.as_bytes());
+            }
+        }
+        let response = request.send().await.map_err(|e| format!("Forward request failed: {}", e))?;
+        let status = response.status().as_u16();
+        let response_headers = response.headers().clone();
+        let response_body = response.bytes().await.map_err(|e| format!("Failed to read forward response: {}", e))?;
+        Ok::<_, String>((status, response_headers, response_body))
+    }
+    .await;
+    result.latency_ms = started.elapsed().as_millis() as u64;
+
+    let response = match outcome {
+        Ok((status, response_headers, response_body)) => {
+            result.status = Some(status);
+            result.headers = response_headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("[invalid header value]").to_string()))
+                .collect();
+            let recorded = &response_body[..response_body.len().min(RECORDED_BODY_LIMIT)];
+            result.body = Some(String::from_utf8_lossy(recorded).into_owned());
+
+            // reqwest and hyper 0.14 use different `http` versions, so rebuild the header map
+            let mut headers = HeaderMap::new();
+            for (name, value) in response_headers.iter() {
+                if let (Ok(name), Ok(value)) = (
+                    hyper::header::HeaderName::from_bytes(name.as_str().as_bytes()),
+                    hyper::header::HeaderValue::from_bytes(value.as_bytes()),
+                ) {
+                    headers.append(name, value);
+                }
+            }
+            let status = StatusCode::from_u16(status).unwrap_or(StatusCode::BAD_GATEWAY);
+            Some((status, headers, response_body))
+        }
+        Err(e) => {
+            result.error = Some(e);
+            None
+        }
+    };
+
+    Forwarded { result, response }
+}
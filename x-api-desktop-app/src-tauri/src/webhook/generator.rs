@@ -0,0 +1,385 @@
+// Synthetic Account Activity deliveries: realistic, correctly signed payloads sent to a
+// webhook URL once or as steady or bursty load, without provoking real account activity.
+
+use super::forward::{self, ForwardConfig, ForwardResult};
+use super::service;
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::{HeaderMap, Method, Uri};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use time::macros::format_description;
+use time::{OffsetDateTime, UtcOffset};
+use tokio::sync::watch;
+use tokio::time::MissedTickBehavior;
+use ts_rs::TS;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SyntheticEventKind {
+    TweetCreate,
+    Favorite,
+    Follow,
+    DirectMessage,
+    TweetDelete,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntheticUser {
+    pub id: String,
+    pub screen_name: String,
+}
+
+// One payload template; unset fields get plausible defaults
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntheticEvent {
+    pub kind: SyntheticEventKind,
+    pub for_user_id: String, // The subscribed user receiving the activity
+    pub actor: Option<SyntheticUser>, // Who performs the action; a random user if unset
+    pub text: Option<String>, // Tweet or message text
+    // Merged into the generated payload last, so any field can be replaced
+    pub overrides: Option<Value>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum LoadPattern {
+    Once,
+    // `rate_per_sec` deliveries a second for `duration_secs`
+    Steady { rate_per_sec: f64, duration_secs: u64 },
+    // `bursts` bursts of `burst_size` (at most MAX_BURST_SIZE) concurrent deliveries, `interval_ms` apart
+    Bursty { burst_size: u32, bursts: u32, interval_ms: u64 },
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratorArgs {
+    pub target_url: String,
+    pub consumer_secret: String,
+    pub events: Vec<SyntheticEvent>, // Used in turn
+    pub load: LoadPattern,
+}
+
+#[derive(Serialize, Clone, TS)]
+pub struct GeneratorProgress {
+    pub sent: u32,
+    pub total: u32,
+    pub result: ForwardResult,
+}
+
+#[derive(Serialize, Clone, TS)]
+pub struct GeneratorReport {
+    pub sent: u32,
+    pub succeeded: u32, // 2xx responses
+    pub failed: u32,
+    #[ts(type = "number")]
+    pub average_latency_ms: u64,
+    pub stopped: bool, // Stopped early on request
+}
+
+fn random_id() -> String {
+    rand::thread_rng().gen_range(1_000_000_000_000_000_000u64..9_000_000_000_000_000_000).to_string()
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn user_object(id: &str, screen_name: &str) -> Value {
+    json!({
+        "id": id.parse::<u64>().map(Value::from).unwrap_or_else(|_| Value::from(id)),
+        "id_str": id,
+        "name": screen_name,
+        "screen_name": screen_name,
+        "protected": false,
+        "verified": false,
+        "followers_count": 42,
+        "friends_count": 7,
+        "statuses_count": 100,
+        "created_at": "Wed Jan 01 00:00:00 +0000 2020",
+    })
+}
+
+fn tweet_object(id: &str, text: &str, author: &Value, created_at: &str) -> Value {
+    json!({
+        "created_at": created_at,
+        "id": id.parse::<u64>().unwrap_or_default(),
+        "id_str": id,
+        "text": text,
+        "source": "<a href=\"https://mobile.twitter.com\" rel=\"nofollow\">Twitter Web App</a>",
+        "truncated": false,
+        "in_reply_to_status_id": null,
+        "in_reply_to_status_id_str": null,
+        "in_reply_to_user_id": null,
+        "in_reply_to_user_id_str": null,
+        "in_reply_to_screen_name": null,
+        "user": author,
+        "is_quote_status": false,
+        "retweet_count": 0,
+        "favorite_count": 0,
+        "entities": {"hashtags": [], "urls": [], "user_mentions": [], "symbols": []},
+        "favorited": false,
+        "retweeted": false,
+        "lang": "en",
+        "timestamp_ms": now_millis().to_string(),
+    })
+}
+
+// RFC 7396 JSON merge patch
+fn merge(target: &mut Value, patch: &Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(key);
+                } else {
+                    merge(target.entry(key.clone()).or_insert(Value::Null), value);
+                }
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+impl SyntheticEvent {
+    pub fn payload(&self) -> Value {
+        let actor = self.actor.clone().unwrap_or_else(|| SyntheticUser {
+            id: random_id(),
+            screen_name: format!("synthetic_{}", rand::thread_rng().gen_range(1000..9999)),
+        });
+        let actor_object = user_object(&actor.id, &actor.screen_name);
+        let subscriber = user_object(&self.for_user_id, "subscribed_user");
+        let created_at = tweet_timestamp(OffsetDateTime::now_utc());
+        let text = self.text.clone().unwrap_or_else(|| "Synthetic event from the X API desktop app".to_string());
+
+        let mut payload = match self.kind {
+            SyntheticEventKind::TweetCreate => json!({
+                "for_user_id": self.for_user_id,
+                "tweet_create_events": [tweet_object(&random_id(), &text, &actor_object, &created_at)],
+            }),
+            SyntheticEventKind::Favorite => json!({
+                "for_user_id": self.for_user_id,
+                "favorite_events": [{
+                    "id": random_id(),
+                    "created_at": created_at,
+                    "timestamp_ms": now_millis(),
+                    "favorited_status": tweet_object(&random_id(), &text, &subscriber, &created_at),
+                    "user": actor_object,
+                }],
+            }),
+            SyntheticEventKind::Follow => json!({
+                "for_user_id": self.for_user_id,
+                "follow_events": [{
+                    "type": "follow",
+                    "created_timestamp": now_millis().to_string(),
+                    "target": {"id": self.for_user_id, "screen_name": "subscribed_user", "name": "subscribed_user"},
+                    "source": {"id": actor.id, "screen_name": actor.screen_name, "name": actor.screen_name},
+                }],
+            }),
+            SyntheticEventKind::DirectMessage => json!({
+                "for_user_id": self.for_user_id,
+                "direct_message_events": [{
+                    "type": "message_create",
+                    "id": random_id(),
+                    "created_timestamp": now_millis().to_string(),
+                    "message_create": {
+                        "target": {"recipient_id": self.for_user_id},
+                        "sender_id": actor.id,
+                        "message_data": {"text": text, "entities": {"hashtags": [], "symbols": [], "user_mentions": [], "urls": []}},
+                    },
+                }],
+                "users": {
+                    actor.id.clone(): {"id": actor.id, "screen_name": actor.screen_name, "name": actor.screen_name},
+                    self.for_user_id.clone(): {"id": self.for_user_id, "screen_name": "subscribed_user", "name": "subscribed_user"},
+                },
+            }),
+            SyntheticEventKind::TweetDelete => json!({
+                "for_user_id": self.for_user_id,
+                "tweet_delete_events": [{
+                    "status": {"id": random_id(), "user_id": actor.id},
+                    "timestamp_ms": now_millis().to_string(),
+                }],
+            }),
+        };
+        if let Some(overrides) = &self.overrides {
+            merge(&mut payload, overrides);
+        }
+        payload
+    }
+}
+
+// Tweet `created_at` format, e.g. "Mon Jan 06 18:20:48 +0000 2025"
+fn tweet_timestamp(at: OffsetDateTime) -> String {
+    let format = format_description!(
+        "[weekday repr:short] [month repr:short] [day] [hour]:[minute]:[second] +0000 [year]"
+    );
+    at.to_offset(UtcOffset::UTC).format(format).unwrap_or_default()
+}
+
+// Signed POST of one payload
+async fn send(client: &reqwest::Client, config: &ForwardConfig, consumer_secret: &str, payload: &Value) -> ForwardResult {
+    let body = payload.to_string();
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    if let Ok(signature) = HeaderValue::from_str(&service::sign(consumer_secret, body.as_bytes())) {
+        headers.insert("x-twitter-webhooks-signature", signature);
+    }
+    forward::forward(client, config, &Method::POST, &Uri::default(), &headers, body.into()).await.result
+}
+
+// Upper bound on concurrent deliveries in one burst, so a typo can't open thousands of sockets
+pub const MAX_BURST_SIZE: u32 = 100;
+
+impl LoadPattern {
+    // (deliveries per batch, number of batches, time from one batch's start to the next)
+    fn schedule(&self) -> (u32, u32, Duration) {
+        match self {
+            LoadPattern::Once => (1, 1, Duration::ZERO),
+            LoadPattern::Steady { rate_per_sec, duration_secs } => {
+                let rate = rate_per_sec.max(0.001);
+                let total = ((rate * *duration_secs as f64).round() as u32).max(1);
+                (1, total, Duration::from_secs_f64(1.0 / rate))
+            }
+            LoadPattern::Bursty { burst_size, bursts, interval_ms } => {
+                ((*burst_size).max(1), (*bursts).max(1), Duration::from_millis(*interval_ms))
+            }
+        }
+    }
+}
+
+// Sends the configured load, reporting every delivery, until done or `stop` is signalled
+pub async fn run(
+    client: &reqwest::Client,
+    args: &GeneratorArgs,
+    mut stop: watch::Receiver<bool>,
+    on_progress: impl Fn(GeneratorProgress),
+) -> Result<GeneratorReport, String> {
+    if args.events.is_empty() {
+        return Err("At least one event template is required".to_string());
+    }
+    if let LoadPattern::Bursty { burst_size, .. } = args.load {
+        if burst_size > MAX_BURST_SIZE {
+            return Err(format!("Bursts can have at most {} deliveries", MAX_BURST_SIZE));
+        }
+    }
+    let config = ForwardConfig { url: args.target_url.clone(), return_downstream: false };
+    let (batch_size, batches, period) = args.load.schedule();
+    let total = batch_size
+        .checked_mul(batches)
+        .ok_or_else(|| format!("Too many deliveries: {} batches of {}", batches, batch_size))?;
+
+    let mut report = GeneratorReport { sent: 0, succeeded: 0, failed: 0, average_latency_ms: 0, stopped: false };
+    let mut total_latency = 0u64;
+    let mut templates = args.events.iter().cycle();
+
+    // Batches start on a fixed schedule, so time spent sending doesn't lower the rate. A late
+    // batch pushes the rest back rather than bunching them up.
+    let mut ticks = tokio::time::interval(period.max(Duration::from_millis(1)));
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    for _ in 0..batches {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            _ = stop.changed() => {
+                report.stopped = true;
+                break;
+            }
+        }
+        if *stop.borrow() {
+            report.stopped = true;
+            break;
+        }
+
+        let payloads: Vec<Value> = (0..batch_size).filter_map(|_| templates.next()).map(SyntheticEvent::payload).collect();
+        let sends = payloads.iter().map(|payload| send(client, &config, &args.consumer_secret, payload));
+        for result in futures::future::join_all(sends).await {
+            report.sent += 1;
+            total_latency += result.latency_ms;
+            if result.status.is_some_and(|status| (200..300).contains(&status)) {
+                report.succeeded += 1;
+            } else {
+                report.failed += 1;
+            }
+            on_progress(GeneratorProgress { sent: report.sent, total, result });
+        }
+    }
+
+    if report.sent > 0 {
+        report.average_latency_ms = total_latency / report.sent as u64;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_tweet_timestamps() {
+        let at = OffsetDateTime::from_unix_timestamp(1_736_187_648).unwrap();
+        assert_eq!(tweet_timestamp(at), "Mon Jan 06 18:20:48 +0000 2025");
+    }
+
+    #[tokio::test]
+    async fn keeps_the_steady_rate_against_a_slow_target() {
+        use hyper::service::{make_service_fn, service_fn};
+        use hyper::{Body, Request, Response, Server};
+        use std::convert::Infallible;
+
+        // Stand-in receiver that takes 40ms to answer each delivery
+        let make_svc = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|_: Request<Body>| async {
+                tokio::time::sleep(Duration::from_millis(40)).await;
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            }))
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let target_url = format!("http://{}/webhook", server.local_addr());
+        tokio::spawn(server);
+
+        let args = GeneratorArgs {
+            target_url,
+            consumer_secret: "secret".to_string(),
+            events: vec![SyntheticEvent {
+                kind: SyntheticEventKind::Follow,
+                for_user_id: "1".to_string(),
+                actor: None,
+                text: None,
+                overrides: None,
+            }],
+            load: LoadPattern::Steady { rate_per_sec: 20.0, duration_secs: 1 },
+        };
+        let (_stop, stop_rx) = watch::channel(false);
+        let started = std::time::Instant::now();
+        let report = run(&reqwest::Client::new(), &args, stop_rx, |_| {}).await.unwrap();
+        assert_eq!((report.sent, report.succeeded), (20, 20));
+        // Sleeping 50ms after each 40ms send would take 1.8s
+        assert!(started.elapsed() < Duration::from_millis(1400), "took {:?}", started.elapsed());
+    }
+
+    #[tokio::test]
+    async fn rejects_loads_too_large_to_count() {
+        let args = GeneratorArgs {
+            target_url: "http://127.0.0.1:9/webhook".to_string(),
+            consumer_secret: "secret".to_string(),
+            events: vec![SyntheticEvent {
+                kind: SyntheticEventKind::Follow,
+                for_user_id: "1".to_string(),
+                actor: None,
+                text: None,
+                overrides: None,
+            }],
+            load: LoadPattern::Bursty { burst_size: MAX_BURST_SIZE, bursts: u32::MAX, interval_ms: 0 },
+        };
+        let (_stop, stop_rx) = watch::channel(false);
+        let error = run(&reqwest::Client::new(), &args, stop_rx.clone(), |_| {}).await.err().unwrap();
+        assert_eq!(error, format!("Too many deliveries: {} batches of 100", u32::MAX));
+
+        let too_wide = GeneratorArgs { load: LoadPattern::Bursty { burst_size: 5_000, bursts: 1, interval_ms: 0 }, ..args };
+        let error = run(&reqwest::Client::new(), &too_wide, stop_rx, |_| {}).await.err().unwrap();
+        assert_eq!(error, "Bursts can have at most 100 deliveries");
+    }
+}
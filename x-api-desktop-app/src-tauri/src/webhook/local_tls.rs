@@ -0,0 +1,231 @@
+// Local HTTPS for the webhook receiver, for testing without a tunnel: a self-signed CA and a
+// leaf certificate for this machine, kept in the app data dir. Test clients trust the exported
+// CA; regenerating issues a new CA, which they have to trust again.
+
+use rcgen::{
+    BasicConstraints, CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+    KeyUsagePurpose,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use time::{Duration, OffsetDateTime};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+
+// Names the leaf certificate covers when none are given
+pub const DEFAULT_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "::1"];
+
+const CA_VALIDITY_DAYS: i64 = 3650;
+// Clients reject server certificates valid for longer than 398 days
+const LEAF_VALIDITY_DAYS: i64 = 397;
+
+const CA_FILE: &str = "ca.pem";
+const CERT_FILE: &str = "server.pem"; // Leaf followed by the CA
+const KEY_FILE: &str = "server.key.pem";
+const INFO_FILE: &str = "certificate.json";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateInfo {
+    pub hosts: Vec<String>, // DNS names and IP addresses the leaf certificate is valid for
+    pub ca_fingerprint: String, // SHA-256 of the CA certificate, as colon-separated hex
+    pub created_at: u64, // Milliseconds since the Unix epoch
+    pub expires_at: u64, // When the leaf certificate expires, ms since the Unix epoch
+    #[serde(default)]
+    pub ca_path: PathBuf, // Where the CA certificate (PEM) is kept
+}
+
+fn unix_millis(at: OffsetDateTime) -> u64 {
+    (at.unix_timestamp_nanos() / 1_000_000) as u64
+}
+
+// Writes `contents` to `path`; `private` files (the server key) are readable by the owner only
+fn write_file(path: &Path, contents: &str, private: bool) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    let mut file = options.open(path)?;
+    // `mode` only applies when the file is created, so tighten one left by an older version too
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents.as_bytes())
+}
+
+fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der).iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<_>>().join(":")
+}
+
+// The certificate files in the app data dir
+#[derive(Default)]
+pub struct LocalCertificates {
+    dir: Option<PathBuf>, // None until the app data dir is known
+}
+
+impl LocalCertificates {
+    pub fn new(dir: PathBuf) -> Self {
+        LocalCertificates { dir: Some(dir) }
+    }
+
+    fn dir(&self) -> Result<&Path, String> {
+        self.dir.as_deref().ok_or_else(|| "The app data directory isn't known yet".to_string())
+    }
+
+    // Path of one certificate file, failing if none have been generated
+    fn existing(&self, file: &str) -> Result<PathBuf, String> {
+        let path = self.dir()?.join(file);
+        if !path.exists() {
+            return Err("No local certificate yet; generate one first".to_string());
+        }
+        Ok(path)
+    }
+
+    pub fn info(&self) -> Result<Option<CertificateInfo>, String> {
+        let Ok(path) = self.existing(INFO_FILE) else { return Ok(None) };
+        let data = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut info: CertificateInfo =
+            serde_json::from_str(&data).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        info.ca_path = self.dir()?.join(CA_FILE);
+        Ok(Some(info))
+    }
+
+    // Issues a new CA and a leaf certificate for `hosts` (DEFAULT_HOSTS if empty), replacing any
+    // previous ones. The CA key isn't kept, so nothing else can be signed with it.
+    pub fn generate(&self, hosts: &[String]) -> Result<CertificateInfo, String> {
+        let dir = self.dir()?;
+        let hosts = if hosts.is_empty() { DEFAULT_HOSTS.map(String::from).to_vec() } else { hosts.to_vec() };
+        let now = OffsetDateTime::now_utc();
+        let expires = now + Duration::days(LEAF_VALIDITY_DAYS);
+
+        let ca_key = KeyPair::generate().map_err(|e| format!("Failed to generate the CA key: {}", e))?;
+        let mut ca_params = CertificateParams::default();
+        ca_params.distinguished_name = DistinguishedName::new();
+        ca_params.distinguished_name.push(DnType::CommonName, "X API Desktop Local CA");
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Constrained(0));
+        ca_params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+        ca_params.not_before = now - Duration::days(1);
+        ca_params.not_after = now + Duration::days(CA_VALIDITY_DAYS);
+        let ca = ca_params.self_signed(&ca_key).map_err(|e| format!("Failed to create the CA certificate: {}", e))?;
+
+        let key = KeyPair::generate().map_err(|e| format!("Failed to generate the server key: {}", e))?;
+        let mut params = CertificateParams::new(hosts.clone()).map_err(|e| format!("Invalid host name: {}", e))?;
+        params.distinguished_name = DistinguishedName::new();
+        params.distinguished_name.push(DnType::CommonName, hosts[0].as_str());
+        params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        params.use_authority_key_identifier_extension = true;
+        params.not_before = now - Duration::days(1);
+        params.not_after = expires;
+        let cert = params
+            .signed_by(&key, &ca, &ca_key)
+            .map_err(|e| format!("Failed to create the server certificate: {}", e))?;
+
+        let info = CertificateInfo {
+            hosts,
+            ca_fingerprint: fingerprint(ca.der()),
+            created_at: unix_millis(now),
+            expires_at: unix_millis(expires),
+            ca_path: dir.join(CA_FILE),
+        };
+        let info_json = serde_json::to_string_pretty(&info).map_err(|e| e.to_string())?;
+
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        for (file, contents, private) in [
+            (CA_FILE, ca.pem(), false),
+            (CERT_FILE, cert.pem() + &ca.pem(), false),
+            (KEY_FILE, key.serialize_pem(), true),
+            (INFO_FILE, info_json, false),
+        ] {
+            let path = dir.join(file);
+            write_file(&path, &contents, private).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        Ok(info)
+    }
+
+    // Copies the CA certificate (PEM) to `path`, for test clients to trust
+    pub fn export_ca(&self, path: &Path) -> Result<(), String> {
+        let ca = self.existing(CA_FILE)?;
+        std::fs::copy(&ca, path).map(|_| ()).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    // TLS settings for serving with the leaf certificate
+    pub fn server_config(&self) -> Result<Arc<ServerConfig>, String> {
+        let cert_path = self.existing(CERT_FILE)?;
+        let certs = CertificateDer::pem_file_iter(&cert_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to read {}: {}", cert_path.display(), e))?;
+        let key_path = self.existing(KEY_FILE)?;
+        let key = PrivateKeyDer::from_pem_file(&key_path)
+            .map_err(|e| format!("Failed to read {}: {}", key_path.display(), e))?;
+
+        let provider = Arc::new(tokio_rustls::rustls::crypto::ring::default_provider());
+        let mut config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| format!("Failed to configure TLS: {}", e))?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map_err(|e| format!("Failed to load the local certificate: {}", e))?;
+        // The receiver only speaks HTTP/1.1
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(Arc::new(config))
+    }
+
+    // HTTP client that trusts the CA, for probing and checking the local HTTPS receiver
+    pub fn client(&self) -> Result<reqwest::Client, String> {
+        let path = self.existing(CA_FILE)?;
+        let pem = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let ca = reqwest::Certificate::from_pem(&pem).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        reqwest::Client::builder()
+            .add_root_certificate(ca)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_and_exports_certificates() {
+        let dir = std::env::temp_dir().join(format!("local-tls-{}", std::process::id()));
+        let certificates = LocalCertificates::new(dir.clone());
+        assert!(certificates.info().unwrap().is_none());
+        assert!(certificates.server_config().is_err());
+
+        let info = certificates.generate(&[]).unwrap();
+        assert_eq!(info.hosts, DEFAULT_HOSTS);
+        assert_eq!(info.ca_fingerprint.len(), 32 * 3 - 1);
+        assert!(info.expires_at > info.created_at);
+        assert_eq!(certificates.info().unwrap().unwrap().ca_fingerprint, info.ca_fingerprint);
+        assert!(certificates.server_config().is_ok());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.join(KEY_FILE)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let exported = dir.join("exported-ca.pem");
+        certificates.export_ca(&exported).unwrap();
+        let exported_ca = CertificateDer::from_pem_file(&exported).unwrap();
+        assert_eq!(fingerprint(&exported_ca), info.ca_fingerprint);
+
+        // Regenerating issues a new CA
+        let info_again = certificates.generate(&["webhooks.test".to_string()]).unwrap();
+        assert_ne!(info_again.ca_fingerprint, info.ca_fingerprint);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,717 @@
+// Webhook receiver: CRC and delivery handling served over a pluggable tunnel provider.
+
+pub mod activity;
+pub mod activity_replay;
+pub mod crc_check;
+pub mod dedup;
+mod forward;
+pub mod generator;
+pub mod local_tls;
+pub mod ngrok_options;
+mod policy;
+mod provider;
+pub mod replay;
+mod server;
+mod service;
+pub mod setup;
+mod supervisor;
+pub mod tunnel_log;
+
+pub use forward::{ForwardConfig, ForwardResult};
+pub use policy::ResponsePolicy;
+pub use provider::ProviderConfig;
+pub use server::TUNNEL_DRAIN_TIMEOUT;
+pub use service::{EndpointConfig, WebhookContext};
+pub use supervisor::{supervise, ProbeResult, SupervisorConfig, SupervisorUpdate, UpdateFn};
+
+use activity::ActivitySummary;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use ts_rs::TS;
+
+// Tunnel id used when the frontend doesn't name an app
+pub const DEFAULT_TUNNEL_ID: &str = "default";
+
+// Payload ids, which X sends as strings or numbers
+fn as_id(value: &Value) -> Option<String> {
+    match value {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+// Progress and error lines from one tunnel
+#[derive(Clone, Serialize, TS)]
+pub struct TunnelMessage {
+    pub tunnel_id: String,
+    pub message: String,
+}
+
+#[derive(Clone, Serialize, TS)]
+pub struct TunnelUrl {
+    pub tunnel_id: String,
+    pub url: String,
+}
+
+// A reconnected tunnel came back at a different public URL, so X must be re-registered
+#[derive(Clone, Serialize, TS)]
+pub struct TunnelUrlChange {
+    pub tunnel_id: String,
+    pub previous_url: String,
+    pub url: String,
+}
+
+// --- Payload for Webhook Event ---
+#[derive(Clone, Serialize, TS)]
+pub struct WebhookRequestInfo {
+    pub tunnel_id: String, // The app whose tunnel received the request
+    pub endpoint_id: Option<String>, // The /webhooks/{id} endpoint it was routed to, if any
+    pub method: String,
+    pub uri: String,
+    pub headers: HashMap<String, String>,
+    pub body: String, // Send body as base64 encoded string for simplicity
+    pub signature_valid: bool, // x-twitter-webhooks-signature matched the body
+    pub activity: Option<ActivitySummary>, // Decoded Account Activity payload, if the body is one
+    pub forward: Option<ForwardResult>, // Downstream response when forwarding is on
+    pub replay_job_id: Option<String>, // Set when an Account Activity Replay job redelivered it
+    pub fingerprint: Option<String>, // Identifies the delivered event(s); None for deliveries stored before it existed
+    pub duplicate: bool, // The same fingerprint already arrived on this tunnel within the dedup window
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::AppEvent;
+    use base64::{Engine as _, engine::general_purpose};
+    use futures::StreamExt;
+    use provider::OpenTunnel;
+    use server::serve;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::sync::watch;
+
+    struct Running {
+        url: String,
+        ctx: Arc<WebhookContext>,
+        events: Arc<Mutex<Vec<AppEvent>>>,
+        shutdown: watch::Sender<bool>,
+        task: tokio::task::JoinHandle<Option<String>>,
+    }
+
+    // Starts the receiver on the local provider with a free port
+    async fn start_local(consumer_secret: &str, reject_invalid_signatures: bool) -> Running {
+        start_with(consumer_secret, |ctx| ctx.endpoint.reject_invalid_signatures = reject_invalid_signatures).await
+    }
+
+    fn test_context(consumer_secret: &str, events: &Arc<Mutex<Vec<AppEvent>>>) -> WebhookContext {
+        let sink_events = events.clone();
+        WebhookContext {
+            tunnel_id: DEFAULT_TUNNEL_ID.to_string(),
+            endpoint: EndpointConfig {
+                consumer_secret: consumer_secret.to_string(),
+                reject_invalid_signatures: false,
+                forward: None,
+            },
+            endpoints: HashMap::new(),
+            policies: Default::default(),
+            client: reqwest::Client::new(),
+            sink: Arc::new(move |event| sink_events.lock().unwrap().push(event)),
+            log: Default::default(),
+            replays: Default::default(),
+            duplicates: Default::default(),
+        }
+    }
+
+    async fn start_with(consumer_secret: &str, configure: impl FnOnce(&mut WebhookContext)) -> Running {
+        let tunnel = ProviderConfig::Local { port: 0 }
+            .into_provider()
+            .open(Box::new(|_| {}))
+            .await
+            .unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut ctx = test_context(consumer_secret, &events);
+        configure(&mut ctx);
+        let ctx = Arc::new(ctx);
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let task = tokio::spawn(serve(tunnel.connections, ctx.clone(), shutdown_rx));
+        Running { url: tunnel.public_url, ctx, events, shutdown, task }
+    }
+
+    #[tokio::test]
+    async fn answers_crc_challenge() {
+        let running = start_local("secret", false).await;
+        let resp = reqwest::get(format!("{}/webhook?crc_token=challenge", running.url)).await.unwrap();
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["response_token"], service::crc_response_token("secret", "challenge"));
+        assert!(body["response_token"].as_str().unwrap().starts_with("sha256="));
+    }
+
+    #[tokio::test]
+    async fn rejects_crc_without_token() {
+        let running = start_local("secret", false).await;
+        let resp = reqwest::get(format!("{}/webhook", running.url)).await.unwrap();
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn emits_delivered_events() {
+        let running = start_local("secret", false).await;
+        let resp = reqwest::Client::new()
+            .post(format!("{}/webhook", running.url))
+            .header("content-type", "application/json")
+            .body(r#"{"for_user_id":"1"}"#)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+
+        let events = running.events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        let AppEvent::NgrokWebhook(info) = &events[0] else { panic!("expected a webhook event") };
+        assert_eq!(info.tunnel_id, DEFAULT_TUNNEL_ID);
+        assert_eq!(info.method, "POST");
+        assert_eq!(info.uri, "/webhook");
+        assert_eq!(info.headers.get("content-type").map(String::as_str), Some("application/json"));
+        assert_eq!(general_purpose::STANDARD.decode(&info.body).unwrap(), br#"{"for_user_id":"1"}"#);
+    }
+
+    #[tokio::test]
+    async fn flags_repeated_deliveries() {
+        let running = start_local("secret", false).await;
+        let event = r#"{"for_user_id":"1","follow_events":[{"id":"e1","type":"follow"}]}"#;
+        for body in [event, r#"{"for_user_id":"1","follow_events":[{"id":"e2","type":"follow"}]}"#, event] {
+            deliver(&running, body, Some(service::sign("secret", body.as_bytes()))).await;
+        }
+
+        let flags: Vec<(bool, String)> = running.events.lock().unwrap().iter().filter_map(|event| match event {
+            AppEvent::NgrokWebhook(info) => Some((info.duplicate, info.fingerprint.clone().unwrap())),
+            _ => None,
+        }).collect();
+        assert_eq!(flags.iter().map(|(duplicate, _)| *duplicate).collect::<Vec<_>>(), vec![false, false, true]);
+        assert_eq!(flags[0].1, flags[2].1);
+        let counts = running.ctx.duplicates.counts();
+        assert_eq!((counts.deliveries, counts.duplicates), (3, 1));
+    }
+
+    #[tokio::test]
+    async fn rejected_deliveries_are_not_counted_as_seen() {
+        let running = start_local("secret", true).await;
+        let event = r#"{"for_user_id":"1","follow_events":[{"id":"e1","type":"follow"}]}"#;
+        assert_eq!(deliver(&running, event, None).await, 401);
+        assert_eq!(deliver(&running, event, Some(service::sign("secret", event.as_bytes()))).await, 200);
+
+        let flags: Vec<bool> = running.events.lock().unwrap().iter().filter_map(|event| match event {
+            AppEvent::NgrokWebhook(info) => Some(info.duplicate),
+            _ => None,
+        }).collect();
+        assert_eq!(flags, [false, false]);
+        let counts = running.ctx.duplicates.counts();
+        assert_eq!((counts.deliveries, counts.duplicates), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn only_signed_deliveries_update_replay_jobs() {
+        use activity_replay::{ReplayJob, ReplayJobState};
+
+        let running = start_local("secret", false).await;
+        let job = ReplayJob::started("1788".to_string(), DEFAULT_TUNNEL_ID.to_string(), "77".to_string(), 0, 1_000);
+        running.ctx.replays.add(job).unwrap();
+        let status = r#"{"replay_job_status":{"job_id":"1788","job_state":"Complete"}}"#;
+        let replayed = r#"{"for_user_id":"1","favorite_events":[{"id":"f1","timestamp_ms":"500"}]}"#;
+
+        assert_eq!(deliver(&running, replayed, None).await, 200);
+        assert_eq!(deliver(&running, status, Some(service::sign("wrong", status.as_bytes()))).await, 200);
+        let jobs = running.ctx.replays.list(None).unwrap();
+        assert_eq!((jobs[0].state, jobs[0].replayed), (ReplayJobState::Running, 0));
+
+        deliver(&running, replayed, Some(service::sign("secret", replayed.as_bytes()))).await;
+        deliver(&running, status, Some(service::sign("secret", status.as_bytes()))).await;
+        let jobs = running.ctx.replays.list(None).unwrap();
+        assert_eq!((jobs[0].state, jobs[0].replayed), (ReplayJobState::Complete, 1));
+
+        let tagged: Vec<Option<String>> = running.events.lock().unwrap().iter().filter_map(|event| match event {
+            AppEvent::NgrokWebhook(info) => Some(info.replay_job_id.clone()),
+            _ => None,
+        }).collect();
+        let job_id = Some("1788".to_string());
+        assert_eq!(tagged, [None, None, job_id.clone(), job_id]);
+    }
+
+    #[tokio::test]
+    async fn serves_crc_and_deliveries_over_local_https() {
+        let dir = std::env::temp_dir().join(format!("local-https-{}", std::process::id()));
+        let certificates = local_tls::LocalCertificates::new(dir.clone());
+        certificates.generate(&[]).unwrap();
+        let tunnel = ProviderConfig::LocalTls { port: 0, tls: Some(certificates.server_config().unwrap()) }
+            .into_provider()
+            .open(Box::new(|_| {}))
+            .await
+            .unwrap();
+        assert!(tunnel.public_url.starts_with("https://127.0.0.1:"));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let ctx = Arc::new(test_context("secret", &events));
+        let (_shutdown, shutdown_rx) = watch::channel(false);
+        tokio::spawn(serve(tunnel.connections, ctx, shutdown_rx));
+
+        // Clients that trust the exported CA get the usual CRC and delivery handling
+        let client = certificates.client().unwrap();
+        let url = format!("{}/webhook", tunnel.public_url);
+        let report = crc_check::check(&client, &url, "secret").await.unwrap();
+        assert!(report.valid, "{:?}", report.problems);
+        let body = r#"{"for_user_id":"1"}"#;
+        let resp = client
+            .post(&url)
+            .header("x-twitter-webhooks-signature", service::sign("secret", body.as_bytes()))
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        assert!(matches!(&events.lock().unwrap()[..], [AppEvent::NgrokWebhook(info)] if info.signature_valid));
+
+        // Others fail the handshake
+        assert!(reqwest::Client::new().get(&url).send().await.is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    async fn deliver(running: &Running, body: &'static str, signature: Option<String>) -> reqwest::StatusCode {
+        let mut request = reqwest::Client::new().post(format!("{}/webhook", running.url)).body(body);
+        if let Some(signature) = signature {
+            request = request.header("x-twitter-webhooks-signature", signature);
+        }
+        request.send().await.unwrap().status()
+    }
+
+    fn delivered(running: &Running) -> Vec<bool> {
+        running.events.lock().unwrap().iter().filter_map(|event| match event {
+            AppEvent::NgrokWebhook(info) => Some(info.signature_valid),
+            _ => None,
+        }).collect()
+    }
+
+    #[tokio::test]
+    async fn tunnels_run_side_by_side_with_their_own_secrets() {
+        let prod = start_with("prod-secret", |ctx| ctx.tunnel_id = "prod".to_string()).await;
+        let staging = start_with("staging-secret", |ctx| ctx.tunnel_id = "staging".to_string()).await;
+        let body = "{}";
+        deliver(&prod, body, Some(service::sign("prod-secret", body.as_bytes()))).await;
+        deliver(&staging, body, Some(service::sign("prod-secret", body.as_bytes()))).await;
+
+        assert_eq!(delivered(&prod), vec![true]);
+        assert_eq!(delivered(&staging), vec![false]);
+        for (running, tunnel_id) in [(&prod, "prod"), (&staging, "staging")] {
+            let events = running.events.lock().unwrap();
+            let AppEvent::NgrokWebhook(info) = &events[0] else { panic!("expected a webhook event") };
+            assert_eq!(info.tunnel_id, tunnel_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn routes_endpoints_by_path_with_their_own_secrets() {
+        let staging = EndpointConfig { consumer_secret: "staging-secret".to_string(), reject_invalid_signatures: true, forward: None };
+        let running = start_with("prod-secret", |ctx| {
+            ctx.endpoints.insert("staging".to_string(), staging);
+        })
+        .await;
+
+        let crc = |path: &str| reqwest::get(format!("{}{}?crc_token=challenge", running.url, path));
+        let body: serde_json::Value = crc("/webhooks/staging").await.unwrap().json().await.unwrap();
+        assert_eq!(body["response_token"], service::crc_response_token("staging-secret", "challenge"));
+        let body: serde_json::Value = crc("/webhook").await.unwrap().json().await.unwrap();
+        assert_eq!(body["response_token"], service::crc_response_token("prod-secret", "challenge"));
+        // Unknown endpoint ids aren't answered with the default endpoint's secret
+        assert_eq!(crc("/webhooks/other").await.unwrap().status(), 404);
+
+        // The staging endpoint rejects a body signed with the default endpoint's secret
+        let client = reqwest::Client::new();
+        let post = |path: &str, secret: &str| {
+            client
+                .post(format!("{}{}", running.url, path))
+                .header("x-twitter-webhooks-signature", service::sign(secret, b"{}"))
+                .body("{}")
+                .send()
+        };
+        assert_eq!(post("/webhooks/staging/", "prod-secret").await.unwrap().status(), 401);
+        assert_eq!(post("/webhooks/staging", "staging-secret").await.unwrap().status(), 200);
+        assert_eq!(post("/webhook", "prod-secret").await.unwrap().status(), 200);
+        assert_eq!(post("/webhooks/stagign", "prod-secret").await.unwrap().status(), 404);
+
+        let routed: Vec<(Option<String>, bool)> = running.events.lock().unwrap().iter().filter_map(|event| match event {
+            AppEvent::NgrokWebhook(info) => Some((info.endpoint_id.clone(), info.signature_valid)),
+            _ => None,
+        }).collect();
+        assert_eq!(routed, vec![(Some("staging".to_string()), false), (Some("staging".to_string()), true), (None, true)]);
+    }
+
+    #[tokio::test]
+    async fn answers_health_checks_without_a_signature() {
+        let running = start_local("secret", true).await;
+        let url = format!("{}{}", running.url, service::HEALTH_PATH);
+        let client = reqwest::Client::new();
+        for request in [client.get(&url), client.post(&url)] {
+            let resp = request.send().await.unwrap();
+            assert_eq!(resp.status(), 200);
+            assert_eq!(resp.json::<serde_json::Value>().await.unwrap()["status"], "ok");
+        }
+        assert!(running.events.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn applies_response_policies_while_running() {
+        let running = start_local("secret", false).await;
+        let ctx = running.ctx.clone();
+        let post = || reqwest::Client::new().post(format!("{}/webhook", running.url)).body("{}").send();
+
+        ctx.policies.set(None, ResponsePolicy { status: Some(503), delay_ms: 200, ..Default::default() }).unwrap();
+        let started = std::time::Instant::now();
+        assert_eq!(post().await.unwrap().status(), 503);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(200));
+
+        ctx.policies.set(None, ResponsePolicy { wrong_crc: true, ..Default::default() }).unwrap();
+        let resp = reqwest::get(format!("{}/webhook?crc_token=challenge", running.url)).await.unwrap();
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_ne!(body["response_token"], service::crc_response_token("secret", "challenge"));
+
+        ctx.policies.set(None, ResponsePolicy { drop_connection: true, ..Default::default() }).unwrap();
+        assert!(post().await.is_err());
+
+        // Deliveries are still shown whatever X was answered
+        ctx.policies.set(None, ResponsePolicy::default()).unwrap();
+        assert_eq!(post().await.unwrap().status(), 200);
+        assert_eq!(delivered(&running).len(), 3);
+        assert!(!running.events.lock().unwrap().iter().any(|event| matches!(event, AppEvent::NgrokError(_))));
+    }
+
+    #[tokio::test]
+    async fn supervisor_reconnects_and_probes_the_new_url() {
+        let ctx = Arc::new(test_context("secret", &Arc::new(Mutex::new(Vec::new()))));
+        let broken = OpenTunnel {
+            public_url: "http://127.0.0.1:9".to_string(),
+            connections: futures::stream::once(async { Err("session lost".to_string()) }).boxed(),
+            close: None,
+        };
+        let config = SupervisorConfig {
+            probe_interval: Some(Duration::from_millis(100)),
+            backoff_initial: Duration::from_millis(10),
+            backoff_max: Duration::from_millis(10),
+        };
+        let (updates_tx, mut updates) = tokio::sync::mpsc::unbounded_channel();
+        let on_update: UpdateFn = Arc::new(move |update| {
+            let _ = updates_tx.send(update);
+        });
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let task = tokio::spawn(supervise(ProviderConfig::Local { port: 0 }, broken, ctx, config, shutdown_rx, on_update));
+
+        let mut disconnected = None;
+        let mut new_url = None;
+        let probe = loop {
+            match tokio::time::timeout(Duration::from_secs(5), updates.recv()).await.unwrap().unwrap() {
+                SupervisorUpdate::Disconnected(error) => disconnected = Some(error),
+                SupervisorUpdate::Reconnected { previous_url, url } => {
+                    assert_eq!(previous_url, "http://127.0.0.1:9");
+                    new_url = Some(url);
+                }
+                SupervisorUpdate::Probe(result) => break result,
+                _ => {}
+            }
+        };
+        assert!(disconnected.unwrap().contains("session lost"));
+        assert_ne!(new_url.unwrap(), "http://127.0.0.1:9");
+        assert!(probe.valid, "{:?}", probe.problems);
+
+        shutdown.send(true).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), task).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn flags_delivery_signatures() {
+        let running = start_local("secret", false).await;
+        let body = r#"{"for_user_id":"1"}"#;
+        assert_eq!(deliver(&running, body, Some(service::sign("secret", body.as_bytes()))).await, 200);
+        assert_eq!(deliver(&running, body, Some(service::sign("other", body.as_bytes()))).await, 200);
+        assert_eq!(deliver(&running, body, None).await, 200);
+        assert_eq!(delivered(&running), vec![true, false, false]);
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_signatures_when_enabled() {
+        let running = start_local("secret", true).await;
+        let body = r#"{"for_user_id":"1"}"#;
+        assert_eq!(deliver(&running, body, Some(service::sign("secret", body.as_bytes()))).await, 200);
+        assert_eq!(deliver(&running, body, Some(service::sign("secret", b"tampered"))).await, 401);
+        assert_eq!(deliver(&running, body, Some("sha256=not base64".to_string())).await, 401);
+        assert_eq!(deliver(&running, body, None).await, 401);
+        assert_eq!(delivered(&running), vec![true, false, false, false]);
+    }
+
+    // Local development server that records what it was sent and answers 202
+    async fn start_downstream() -> (String, Arc<Mutex<Vec<(String, String, String)>>>) {
+        use hyper::service::{make_service_fn, service_fn};
+        use hyper::{Body, Request, Response, Server};
+        use std::convert::Infallible;
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let server_received = received.clone();
+        let make_svc = make_service_fn(move |_| {
+            let received = server_received.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let received = received.clone();
+                    async move {
+                        let uri = req.uri().to_string();
+                        let signature = req.headers().get("x-twitter-webhooks-signature")
+                            .map(|v| v.to_str().unwrap().to_string())
+                            .unwrap_or_default();
+                        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                        received.lock().unwrap().push((uri, signature, String::from_utf8_lossy(&body).to_string()));
+                        Ok::<_, Infallible>(Response::builder().status(202).header("x-downstream", "yes").body(Body::from("queued")).unwrap())
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let url = format!("http://{}/webhook", server.local_addr());
+        tokio::spawn(server);
+        (url, received)
+    }
+
+    #[tokio::test]
+    async fn forwards_deliveries_and_records_the_response() {
+        let (downstream, received) = start_downstream().await;
+        let forward = ForwardConfig { url: downstream.clone(), return_downstream: false };
+        let running = start_with("secret", |ctx| ctx.endpoint.forward = Some(forward)).await;
+
+        let body = r#"{"for_user_id":"1"}"#;
+        let signature = service::sign("secret", body.as_bytes());
+        assert_eq!(deliver(&running, body, Some(signature.clone())).await, 200);
+        assert_eq!(received.lock().unwrap()[0], ("/webhook".to_string(), signature, body.to_string()));
+
+        let events = running.events.lock().unwrap();
+        let AppEvent::NgrokWebhook(info) = &events[0] else { panic!("expected a webhook event") };
+        let result = info.forward.as_ref().expect("forward result recorded");
+        assert_eq!(result.url, downstream);
+        assert_eq!(result.status, Some(202));
+        assert_eq!(result.body.as_deref(), Some("queued"));
+        assert!(result.headers.iter().any(|(k, v)| k == "x-downstream" && v == "yes"));
+    }
+
+    #[tokio::test]
+    async fn returns_the_downstream_response_when_configured() {
+        let (downstream, received) = start_downstream().await;
+        let forward = ForwardConfig { url: downstream, return_downstream: true };
+        let running = start_with("secret", |ctx| ctx.endpoint.forward = Some(forward)).await;
+
+        let resp = reqwest::get(format!("{}/webhook?crc_token=abc", running.url)).await.unwrap();
+        assert_eq!(resp.status(), 202);
+        assert_eq!(resp.headers()["x-downstream"], "yes");
+        assert_eq!(resp.text().await.unwrap(), "queued");
+        assert_eq!(received.lock().unwrap()[0].0, "/webhook?crc_token=abc");
+
+        assert_eq!(deliver(&running, "{}", None).await, 202);
+    }
+
+    #[tokio::test]
+    async fn reports_unreachable_downstream() {
+        let forward = ForwardConfig { url: "http://127.0.0.1:9/webhook".to_string(), return_downstream: true };
+        let running = start_with("secret", |ctx| ctx.endpoint.forward = Some(forward)).await;
+        assert_eq!(deliver(&running, "{}", None).await, 502);
+
+        let events = running.events.lock().unwrap();
+        let AppEvent::NgrokWebhook(info) = &events[0] else { panic!("expected a webhook event") };
+        let result = info.forward.as_ref().unwrap();
+        assert_eq!(result.status, None);
+        assert!(result.error.is_some());
+
+        let query = tunnel_log::LogQuery { min_level: Some(tunnel_log::LogLevel::Warn), ..Default::default() };
+        let page = running.ctx.log.query(&query).unwrap();
+        assert!(page.entries.iter().any(|entry| entry.message.starts_with("Forwarding to http://127.0.0.1:9/webhook failed")));
+    }
+
+    #[tokio::test]
+    async fn replays_stored_events_with_a_fresh_signature() {
+        use crate::event_store::{EventFilter, EventStore};
+        use replay::{Pacing, ReplayArgs};
+
+        let (downstream, received) = start_downstream().await;
+        let store = EventStore::default();
+        let mut original = WebhookRequestInfo {
+            tunnel_id: DEFAULT_TUNNEL_ID.to_string(),
+            endpoint_id: None,
+            method: "POST".to_string(),
+            uri: "/webhooks/1?source=prod".to_string(),
+            headers: HashMap::from([
+                ("content-type".to_string(), "application/json".to_string()),
+                ("x-twitter-webhooks-signature".to_string(), service::sign("prod-secret", b"{\"n\":1}")),
+            ]),
+            body: general_purpose::STANDARD.encode(b"{\"n\":1}"),
+            signature_valid: true,
+            activity: None,
+            forward: None,
+            replay_job_id: None,
+            fingerprint: None,
+            duplicate: false,
+        };
+        let first = store.insert(&original).unwrap();
+        original.body = general_purpose::STANDARD.encode(b"{\"n\":2}");
+        let second = store.insert(&original).unwrap();
+        let events = store.get_many(&[second, first]).unwrap();
+        assert_eq!(events.iter().map(|e| e.id).collect::<Vec<_>>(), vec![first, second]);
+        assert_eq!(store.query(&EventFilter::default()).unwrap().total, 2);
+
+        let args = ReplayArgs {
+            event_ids: vec![first, second],
+            target_url: downstream,
+            consumer_secret: Some("dev-secret".to_string()),
+            pacing: Pacing::Fixed { interval_ms: 10 },
+        };
+        let reported = Mutex::new(Vec::new());
+        let outcomes = replay::replay(&reqwest::Client::new(), &events, &args, |outcome| {
+            reported.lock().unwrap().push(outcome.event_id)
+        })
+        .await;
+
+        assert_eq!(*reported.lock().unwrap(), vec![first, second]);
+        assert!(outcomes.iter().all(|outcome| outcome.result.status == Some(202)));
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        for ((uri, signature, body), expected) in received.iter().zip([r#"{"n":1}"#, r#"{"n":2}"#]) {
+            assert_eq!(uri, "/webhook?source=prod");
+            assert_eq!(body, expected);
+            assert!(service::verify_signature("dev-secret", body.as_bytes(), Some(signature)));
+        }
+    }
+
+    #[tokio::test]
+    async fn generates_signed_account_activity() {
+        use generator::{GeneratorArgs, LoadPattern, SyntheticEvent, SyntheticEventKind, SyntheticUser};
+
+        let running = start_local("secret", true).await;
+        let template = |kind| SyntheticEvent {
+            kind,
+            for_user_id: "2244994945".to_string(),
+            actor: Some(SyntheticUser { id: "6253282".to_string(), screen_name: "api".to_string() }),
+            text: Some("hello".to_string()),
+            overrides: None,
+        };
+        let kinds = [
+            (SyntheticEventKind::TweetCreate, "tweet_create_events"),
+            (SyntheticEventKind::Favorite, "favorite_events"),
+            (SyntheticEventKind::Follow, "follow_events"),
+            (SyntheticEventKind::DirectMessage, "direct_message_events"),
+            (SyntheticEventKind::TweetDelete, "tweet_delete_events"),
+        ];
+        let args = GeneratorArgs {
+            target_url: format!("{}/webhook", running.url),
+            consumer_secret: "secret".to_string(),
+            events: kinds.iter().map(|(kind, _)| template(*kind)).collect(),
+            load: LoadPattern::Bursty { burst_size: 5, bursts: 1, interval_ms: 0 },
+        };
+        let (_stop, stop_rx) = watch::channel(false);
+        let report = generator::run(&reqwest::Client::new(), &args, stop_rx, |_| {}).await.unwrap();
+        assert_eq!((report.sent, report.succeeded, report.failed), (5, 5, 0));
+
+        let events = running.events.lock().unwrap();
+        let mut decoded: Vec<_> = events.iter().map(|event| {
+            let AppEvent::NgrokWebhook(info) = event else { panic!("expected a webhook event") };
+            assert!(info.signature_valid);
+            let activity = info.activity.as_ref().expect("payload decodes as Account Activity");
+            assert!(activity.errors.is_empty() && activity.unknown_keys.is_empty());
+            assert_eq!(activity.for_user_id.as_deref(), Some("2244994945"));
+            let event = &activity.events[0];
+            assert_eq!(event.actor.as_ref().map(|a| a.id.as_str()), Some("6253282"));
+            event.event_type.clone()
+        }).collect();
+        decoded.sort();
+        let mut expected: Vec<_> = kinds.iter().map(|(_, key)| key.to_string()).collect();
+        expected.sort();
+        assert_eq!(decoded, expected);
+    }
+
+    #[tokio::test]
+    async fn generator_applies_overrides_and_stops_on_request() {
+        use generator::{GeneratorArgs, LoadPattern, SyntheticEvent, SyntheticEventKind};
+
+        let running = start_local("secret", false).await;
+        let args = GeneratorArgs {
+            target_url: format!("{}/webhook", running.url),
+            consumer_secret: "secret".to_string(),
+            events: vec![SyntheticEvent {
+                kind: SyntheticEventKind::Follow,
+                for_user_id: "1".to_string(),
+                actor: None,
+                text: None,
+                overrides: Some(serde_json::json!({"for_user_id": "99", "user_has_blocked": true})),
+            }],
+            load: LoadPattern::Steady { rate_per_sec: 20.0, duration_secs: 60 },
+        };
+        let (stop, stop_rx) = watch::channel(false);
+        let report = generator::run(&reqwest::Client::new(), &args, stop_rx, |progress| {
+            if progress.sent == 3 {
+                stop.send(true).unwrap();
+            }
+        })
+        .await
+        .unwrap();
+        assert!(report.stopped);
+        assert_eq!(report.sent, 3);
+
+        let events = running.events.lock().unwrap();
+        let AppEvent::NgrokWebhook(info) = &events[0] else { panic!("expected a webhook event") };
+        let activity = info.activity.as_ref().unwrap();
+        assert_eq!(activity.for_user_id.as_deref(), Some("99"));
+        assert_eq!(activity.user_has_blocked, Some(true));
+    }
+
+    #[tokio::test]
+    async fn crc_check_validates_our_own_receiver() {
+        let running = start_local("secret", false).await;
+        let client = reqwest::Client::new();
+        let url = format!("{}/webhook", running.url);
+
+        let report = crc_check::check(&client, &url, "secret").await.unwrap();
+        assert!(report.valid, "{:?}", report.problems);
+        assert_eq!(report.status, Some(200));
+        assert_eq!(report.response_token.as_deref(), Some(report.expected_token.as_str()));
+        assert_eq!(report.problems, vec!["X only registers https:// webhook URLs".to_string()]);
+
+        let report = crc_check::check(&client, &url, "wrong").await.unwrap();
+        assert!(!report.valid);
+        assert!(report.problems.iter().any(|p| p.contains("does not match")));
+    }
+
+    #[tokio::test]
+    async fn crc_check_reports_bad_responses() {
+        let (downstream, _) = start_downstream().await;
+        let report = crc_check::check(&reqwest::Client::new(), &downstream, "secret").await.unwrap();
+        assert!(!report.valid);
+        assert_eq!(report.status, Some(202));
+        assert!(report.problems.iter().any(|p| p.contains("Expected HTTP 200")));
+        assert!(report.problems.iter().any(|p| p.contains("Content-Type")));
+        assert!(report.problems.iter().any(|p| p.contains("not JSON")));
+
+        let report = crc_check::check(&reqwest::Client::new(), "http://127.0.0.1:9/webhook", "secret").await.unwrap();
+        assert!(!report.valid);
+        assert!(report.problems.iter().any(|p| p.starts_with("Request failed")));
+    }
+
+    #[tokio::test]
+    async fn rejects_other_methods() {
+        let running = start_local("secret", false).await;
+        let resp = reqwest::Client::new().put(format!("{}/webhook", running.url)).send().await.unwrap();
+        assert_eq!(resp.status(), 405);
+        assert!(running.events.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn stops_on_shutdown() {
+        let running = start_local("secret", false).await;
+        running.shutdown.send(true).unwrap();
+        let result = tokio::time::timeout(TUNNEL_DRAIN_TIMEOUT, running.task).await.unwrap().unwrap();
+        assert_eq!(result, None);
+        assert!(reqwest::get(format!("{}/webhook?crc_token=x", running.url)).await.is_err());
+    }
+}
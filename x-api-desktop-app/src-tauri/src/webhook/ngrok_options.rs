@@ -0,0 +1,242 @@
+// ngrok endpoint options (static domain, region, access control, headers, metadata) and their
+// per-app store, persisted as JSON in the app data dir so a registered webhook URL survives
+// restarts.
+
+use ngrok::config::{HttpTunnelBuilder, OauthOptions};
+use ngrok::session::SessionBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+// ngrok rejects basic auth passwords outside this length range
+const BASIC_AUTH_PASSWORD_LEN: std::ops::RangeInclusive<usize> = 8..=128;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BasicAuth {
+    pub username: String,
+    // Never written to disk or sent back to the frontend, so it's entered again each session
+    #[serde(default, skip_serializing)]
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuth {
+    pub provider: String, // e.g. "google" or "github"
+    #[serde(default)]
+    pub allow_emails: Vec<String>,
+    #[serde(default)]
+    pub allow_domains: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NgrokOptions {
+    pub domain: Option<String>, // Reserved or static domain, e.g. "my-app.ngrok.app"
+    pub region: Option<String>, // e.g. "eu"; ngrok picks the closest when unset
+    pub basic_auth: Option<BasicAuth>,
+    pub oauth: Option<OAuth>,
+    pub allow_cidrs: Vec<String>,
+    pub deny_cidrs: Vec<String>,
+    pub request_headers: BTreeMap<String, String>, // Added to every request before it reaches us
+    pub metadata: Option<String>,
+}
+
+fn validate_cidr(cidr: &str) -> Result<(), String> {
+    let invalid = || format!("Invalid CIDR '{}'", cidr);
+    let (addr, prefix) = cidr.split_once('/').ok_or_else(invalid)?;
+    let addr: IpAddr = addr.parse().map_err(|_| invalid())?;
+    let prefix: u8 = prefix.parse().map_err(|_| invalid())?;
+    let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+    if prefix > max_prefix {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+impl NgrokOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(region) = &self.region {
+            if region.is_empty() || !region.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+                return Err(format!("Invalid ngrok region '{}'", region));
+            }
+        }
+        if let Some(auth) = &self.basic_auth {
+            if !auth.username.is_empty() && auth.password.is_empty() {
+                return Err("Enter the basic auth password; it isn't saved between sessions".to_string());
+            }
+            if auth.username.is_empty() || !BASIC_AUTH_PASSWORD_LEN.contains(&auth.password.len()) {
+                return Err("Basic auth needs a username and a password of 8 to 128 characters".to_string());
+            }
+        }
+        if self.basic_auth.is_some() && self.oauth.is_some() {
+            return Err("Use either basic auth or OAuth, not both".to_string());
+        }
+        for cidr in self.allow_cidrs.iter().chain(&self.deny_cidrs) {
+            validate_cidr(cidr)?;
+        }
+        Ok(())
+    }
+
+    pub fn apply_to_session(&self, mut session: SessionBuilder) -> SessionBuilder {
+        if let Some(region) = &self.region {
+            session = session.server_addr(format!("tunnel.{}.ngrok.com:443", region));
+        }
+        session
+    }
+
+    pub fn apply_to_endpoint(&self, mut endpoint: HttpTunnelBuilder) -> HttpTunnelBuilder {
+        if let Some(domain) = &self.domain {
+            endpoint = endpoint.domain(domain);
+        }
+        if let Some(auth) = &self.basic_auth {
+            endpoint = endpoint.basic_auth(&auth.username, &auth.password);
+        }
+        if let Some(oauth) = &self.oauth {
+            let mut options = OauthOptions::new(&oauth.provider);
+            for email in &oauth.allow_emails {
+                options = options.allow_email(email);
+            }
+            for domain in &oauth.allow_domains {
+                options = options.allow_domain(domain);
+            }
+            endpoint = endpoint.oauth(options);
+        }
+        for cidr in &self.allow_cidrs {
+            endpoint = endpoint.allow_cidr(cidr);
+        }
+        for cidr in &self.deny_cidrs {
+            endpoint = endpoint.deny_cidr(cidr);
+        }
+        for (name, value) in &self.request_headers {
+            endpoint = endpoint.request_header(name, value);
+        }
+        if let Some(metadata) = &self.metadata {
+            endpoint = endpoint.metadata(metadata);
+        }
+        endpoint
+    }
+}
+
+// Saved options, keyed by tunnel (app) id
+#[derive(Default)]
+pub struct NgrokOptionsStore {
+    options: BTreeMap<String, NgrokOptions>,
+    path: Option<PathBuf>, // None until the app data dir is known
+}
+
+impl NgrokOptionsStore {
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let mut store = NgrokOptionsStore::default();
+        if path.exists() {
+            let data = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            store.options = serde_json::from_str(&data)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        }
+        store.path = Some(path);
+        Ok(store)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let data = serde_json::to_string_pretty(&self.options).map_err(|e| e.to_string())?;
+        std::fs::write(path, data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn get(&self, tunnel_id: &str) -> Option<&NgrokOptions> {
+        self.options.get(tunnel_id)
+    }
+
+    // A blank basic auth password keeps the one entered earlier this session for the same user
+    pub fn upsert(&mut self, tunnel_id: &str, mut options: NgrokOptions) -> Result<(), String> {
+        if let (Some(auth), Some(saved)) = (
+            options.basic_auth.as_mut(),
+            self.options.get(tunnel_id).and_then(|o| o.basic_auth.as_ref()),
+        ) {
+            if auth.password.is_empty() && auth.username == saved.username {
+                auth.password = saved.password.clone();
+            }
+        }
+        options.validate()?;
+        self.options.insert(tunnel_id.to_string(), options);
+        self.save()
+    }
+
+    pub fn remove(&mut self, tunnel_id: &str) -> Result<(), String> {
+        if self.options.remove(tunnel_id).is_none() {
+            return Err(format!("No ngrok options saved for '{}'", tunnel_id));
+        }
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_options() {
+        let valid = NgrokOptions {
+            region: Some("eu".to_string()),
+            basic_auth: Some(BasicAuth { username: "x".to_string(), password: "long enough".to_string() }),
+            allow_cidrs: vec!["203.0.113.0/24".to_string(), "2001:db8::/32".to_string()],
+            ..Default::default()
+        };
+        assert!(valid.validate().is_ok());
+
+        let short_password = NgrokOptions {
+            basic_auth: Some(BasicAuth { username: "x".to_string(), password: "short".to_string() }),
+            ..Default::default()
+        };
+        assert!(short_password.validate().is_err());
+        for cidr in ["203.0.113.0", "203.0.113.0/33", "nope/8"] {
+            let options = NgrokOptions { deny_cidrs: vec![cidr.to_string()], ..Default::default() };
+            assert!(options.validate().is_err(), "{} should be rejected", cidr);
+        }
+        assert!(NgrokOptions { region: Some("EU west".to_string()), ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn persists_options_per_app() {
+        let path = std::env::temp_dir().join(format!("ngrok-options-{}.json", std::process::id()));
+        let mut store = NgrokOptionsStore::load(path.clone()).unwrap();
+        let options = NgrokOptions { domain: Some("prod.ngrok.app".to_string()), ..Default::default() };
+        store.upsert("prod", options).unwrap();
+        assert!(store.upsert("staging", NgrokOptions { allow_cidrs: vec!["bad".to_string()], ..Default::default() }).is_err());
+
+        let reloaded = NgrokOptionsStore::load(path.clone()).unwrap();
+        assert_eq!(reloaded.get("prod").and_then(|o| o.domain.as_deref()), Some("prod.ngrok.app"));
+        assert!(reloaded.get("staging").is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn keeps_basic_auth_passwords_for_the_session_only() {
+        let path = std::env::temp_dir().join(format!("ngrok-options-auth-{}.json", std::process::id()));
+        let mut store = NgrokOptionsStore::load(path.clone()).unwrap();
+        let auth = |password: &str| NgrokOptions {
+            basic_auth: Some(BasicAuth { username: "x".to_string(), password: password.to_string() }),
+            ..Default::default()
+        };
+        store.upsert("prod", auth("long enough")).unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("long enough"));
+
+        // Saving again without the password keeps the one entered this session
+        store.upsert("prod", auth("")).unwrap();
+        assert_eq!(store.get("prod").and_then(|o| o.basic_auth.as_ref()).unwrap().password, "long enough");
+
+        let mut reloaded = NgrokOptionsStore::load(path.clone()).unwrap();
+        let saved = reloaded.get("prod").and_then(|o| o.basic_auth.as_ref()).unwrap();
+        assert_eq!((saved.username.as_str(), saved.password.as_str()), ("x", ""));
+        assert_eq!(
+            reloaded.upsert("prod", auth("")).unwrap_err(),
+            "Enter the basic auth password; it isn't saved between sessions"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
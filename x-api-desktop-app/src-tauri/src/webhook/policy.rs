@@ -0,0 +1,140 @@
+// Response policies for simulating a failing webhook server, so X's retries and webhook
+// invalidation can be watched against a real integration. Policies are per endpoint and can
+// be swapped while the tunnel runs.
+
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Status used for the failures `fail_every` injects
+const INJECTED_FAILURE_STATUS: StatusCode = StatusCode::INTERNAL_SERVER_ERROR;
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ResponsePolicy {
+    pub status: Option<u16>, // Answer every request with this status and an empty body
+    pub delay_ms: u64, // Added before answering
+    pub drop_connection: bool, // Close the connection without answering
+    pub wrong_crc: bool, // Answer CRC checks with a response_token that doesn't match
+    pub fail_every: Option<u32>, // Answer every Nth delivery with a 500
+}
+
+impl ResponsePolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(status) = self.status {
+            StatusCode::from_u16(status).map_err(|_| format!("Invalid status code {}", status))?;
+        }
+        if self.fail_every == Some(0) {
+            return Err("fail_every must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+// How a policy changes the response to one request
+#[derive(Default, PartialEq, Debug)]
+pub struct Decision {
+    pub delay: Duration,
+    pub drop_connection: bool,
+    pub status: Option<StatusCode>, // Replaces the normal response
+    pub wrong_crc: bool,
+}
+
+// Returned from the service to make hyper close the connection without a response
+#[derive(Debug)]
+pub struct ConnectionDropped;
+
+impl fmt::Display for ConnectionDropped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "connection dropped by response policy")
+    }
+}
+
+impl std::error::Error for ConnectionDropped {}
+
+// Policies of one listener, keyed by endpoint id (None for the default endpoint)
+#[derive(Default)]
+pub struct Policies {
+    endpoints: Mutex<HashMap<Option<String>, (ResponsePolicy, u64)>>, // Policy and deliveries since it was set
+}
+
+impl Policies {
+    pub fn set(&self, endpoint_id: Option<String>, policy: ResponsePolicy) -> Result<(), String> {
+        policy.validate()?;
+        let mut guard = self.endpoints.lock().map_err(|e| format!("Mutex lock error: {}", e))?;
+        if policy == ResponsePolicy::default() {
+            guard.remove(&endpoint_id);
+        } else {
+            guard.insert(endpoint_id, (policy, 0));
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, endpoint_id: Option<&str>) -> ResponsePolicy {
+        self.endpoints
+            .lock()
+            .ok()
+            .and_then(|guard| guard.get(&endpoint_id.map(str::to_string)).map(|(policy, _)| policy.clone()))
+            .unwrap_or_default()
+    }
+
+    // Decides how to answer a request to the endpoint, counting it if it's a delivery
+    pub fn decide(&self, endpoint_id: Option<&str>, is_delivery: bool) -> Decision {
+        let Ok(mut guard) = self.endpoints.lock() else { return Decision::default() };
+        let Some((policy, deliveries)) = guard.get_mut(&endpoint_id.map(str::to_string)) else {
+            return Decision::default();
+        };
+        let mut status = policy.status.and_then(|status| StatusCode::from_u16(status).ok());
+        if is_delivery {
+            *deliveries += 1;
+            if policy.fail_every.is_some_and(|n| *deliveries % n as u64 == 0) {
+                status = Some(INJECTED_FAILURE_STATUS);
+            }
+        }
+        Decision {
+            delay: Duration::from_millis(policy.delay_ms),
+            drop_connection: policy.drop_connection,
+            status,
+            wrong_crc: policy.wrong_crc && !is_delivery,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fails_every_nth_delivery_per_endpoint() {
+        let policies = Policies::default();
+        policies.set(Some("staging".to_string()), ResponsePolicy { fail_every: Some(3), ..Default::default() }).unwrap();
+
+        let statuses: Vec<Option<StatusCode>> =
+            (0..6).map(|_| policies.decide(Some("staging"), true).status).collect();
+        let failure = Some(INJECTED_FAILURE_STATUS);
+        assert_eq!(statuses, vec![None, None, failure, None, None, failure]);
+
+        // CRC checks and other endpoints are unaffected
+        assert_eq!(policies.decide(Some("staging"), false), Decision::default());
+        assert_eq!(policies.decide(None, true), Decision::default());
+    }
+
+    #[test]
+    fn replacing_or_clearing_a_policy_resets_it() {
+        let policies = Policies::default();
+        let policy = ResponsePolicy { fail_every: Some(2), wrong_crc: true, ..Default::default() };
+        policies.set(None, policy.clone()).unwrap();
+        policies.decide(None, true);
+        policies.set(None, policy.clone()).unwrap();
+        assert_eq!(policies.decide(None, true).status, None);
+        assert!(policies.decide(None, false).wrong_crc);
+
+        policies.set(None, ResponsePolicy::default()).unwrap();
+        assert_eq!(policies.get(None), ResponsePolicy::default());
+        assert!(policies.set(None, ResponsePolicy { status: Some(1000), ..Default::default() }).is_err());
+        assert!(policies.set(None, ResponsePolicy { fail_every: Some(0), ..Default::default() }).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+// Tunnel providers: where webhook connections come from and which public URL reaches them.
+
+use futures::future::BoxFuture;
+use super::ngrok_options::NgrokOptions;
+use futures::stream::{BoxStream, StreamExt};
+use ngrok::config::TunnelBuilder;
+use ngrok::tunnel::UrlTunnel;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
+
+// How long a client gets to finish the TLS handshake on the local HTTPS listener
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Pause before accepting again after running out of file descriptors or memory
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+#[cfg(unix)]
+const ENFILE: i32 = 23;
+#[cfg(unix)]
+const EMFILE: i32 = 24;
+
+// How long to wait before accepting again, or None if the listener itself is broken. Errors
+// that only hit one connection are retried at once; descriptor or memory shortages after a pause.
+fn accept_retry_delay(e: &std::io::Error) -> Option<Duration> {
+    use std::io::ErrorKind;
+    match e.kind() {
+        ErrorKind::ConnectionAborted
+        | ErrorKind::ConnectionReset
+        | ErrorKind::ConnectionRefused
+        | ErrorKind::Interrupted
+        | ErrorKind::TimedOut => Some(Duration::ZERO),
+        ErrorKind::OutOfMemory => Some(ACCEPT_BACKOFF),
+        #[cfg(unix)]
+        _ if matches!(e.raw_os_error(), Some(EMFILE | ENFILE)) => Some(ACCEPT_BACKOFF),
+        _ => None,
+    }
+}
+
+// Accepts the next connection, reporting and riding out errors that don't break the listener
+async fn accept(listener: &TcpListener, progress: &ProgressFn) -> Result<TcpStream, String> {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => return Ok(stream),
+            Err(e) => {
+                let Some(delay) = accept_retry_delay(&e) else { return Err(e.to_string()) };
+                progress(format!("Failed to accept a connection, still listening: {}", e));
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+// A single accepted connection, whatever transport it arrived on
+pub trait WebhookConn: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + 'static> WebhookConn for T {}
+
+pub type ConnStream = BoxStream<'static, Result<Box<dyn WebhookConn>, String>>;
+
+// Reports progress while a provider is opening
+pub type ProgressFn = Box<dyn Fn(String) + Send + Sync>;
+
+pub struct OpenTunnel {
+    pub public_url: String,
+    pub connections: ConnStream,
+    // Closes the provider's session when awaited, if it has one
+    pub close: Option<BoxFuture<'static, Result<(), String>>>,
+}
+
+pub trait TunnelProvider: Send {
+    fn open(self: Box<Self>, progress: ProgressFn) -> BoxFuture<'static, Result<OpenTunnel, String>>;
+}
+
+// Provider selection as sent by the frontend
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ProviderConfig {
+    // Public HTTPS endpoint through an ngrok session. Without options, the ones saved for the
+    // tunnel are used; given options are saved for next time.
+    Ngrok { auth_token: String, options: Option<Box<NgrokOptions>> },
+    // Plain HTTP on 127.0.0.1:<port>; port 0 picks a free one
+    Local { port: u16 },
+    // HTTPS on 127.0.0.1:<port> with the app's local certificate, filled in when the tunnel starts
+    LocalTls {
+        port: u16,
+        #[serde(skip)]
+        tls: Option<Arc<ServerConfig>>,
+    },
+    // Bound locally, reachable through a tunnel or proxy managed outside the app
+    External { public_url: String, port: u16 },
+}
+
+impl ProviderConfig {
+    pub fn into_provider(self) -> Box<dyn TunnelProvider> {
+        match self {
+            ProviderConfig::Ngrok { auth_token, options } => Box::new(NgrokProvider {
+                auth_token,
+                options: options.map(|options| *options).unwrap_or_default(),
+            }),
+            ProviderConfig::Local { port } => Box::new(LocalProvider {
+                addr: SocketAddr::from(([127, 0, 0, 1], port)),
+                public_url: None,
+            }),
+            ProviderConfig::LocalTls { port, tls } => Box::new(LocalTlsProvider {
+                addr: SocketAddr::from(([127, 0, 0, 1], port)),
+                tls,
+            }),
+            ProviderConfig::External { public_url, port } => Box::new(LocalProvider {
+                addr: SocketAddr::from(([127, 0, 0, 1], port)),
+                public_url: Some(public_url),
+            }),
+        }
+    }
+}
+
+pub struct NgrokProvider {
+    pub auth_token: String,
+    pub options: NgrokOptions,
+}
+
+impl TunnelProvider for NgrokProvider {
+    fn open(self: Box<Self>, progress: ProgressFn) -> BoxFuture<'static, Result<OpenTunnel, String>> {
+        Box::pin(async move {
+            progress("Starting ngrok session...".to_string());
+            let session = self.options
+                .apply_to_session(ngrok::Session::builder().authtoken(self.auth_token))
+                .connect()
+                .await
+                .map_err(|e| format!("Failed to connect session: {}", e))?;
+            progress("Ngrok session connected.".to_string());
+
+            progress("Starting HTTP tunnel...".to_string());
+            let listener = self.options
+                .apply_to_endpoint(session.http_endpoint())
+                .listen()
+                .await
+                .map_err(|e| format!("Failed to start listener: {}", e))?;
+            let public_url = listener.url().to_string();
+
+            let connections = listener
+                .map(|conn| conn.map(|c| Box::new(c) as Box<dyn WebhookConn>).map_err(|e| e.to_string()))
+                .boxed();
+            let mut session = session;
+            let close = Box::pin(async move {
+                session.close().await.map_err(|e| format!("Failed to close ngrok session: {}", e))
+            });
+
+            Ok(OpenTunnel { public_url, connections, close: Some(close) })
+        })
+    }
+}
+
+pub struct LocalProvider {
+    pub addr: SocketAddr,
+    // Reported instead of the local address when something else fronts the listener
+    pub public_url: Option<String>,
+}
+
+impl TunnelProvider for LocalProvider {
+    fn open(self: Box<Self>, progress: ProgressFn) -> BoxFuture<'static, Result<OpenTunnel, String>> {
+        Box::pin(async move {
+            let listener = TcpListener::bind(self.addr)
+                .await
+                .map_err(|e| format!("Failed to bind {}: {}", self.addr, e))?;
+            let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
+            progress(format!("Listening locally on {}", local_addr));
+
+            let public_url = self.public_url.unwrap_or_else(|| format!("http://{}", local_addr));
+            let connections = futures::stream::unfold((listener, progress), |(listener, progress)| async move {
+                let conn = accept(&listener, &progress).await.map(|stream| Box::new(stream) as Box<dyn WebhookConn>);
+                Some((conn, (listener, progress)))
+            })
+            .boxed();
+
+            Ok(OpenTunnel { public_url, connections, close: None })
+        })
+    }
+}
+
+pub struct LocalTlsProvider {
+    pub addr: SocketAddr,
+    pub tls: Option<Arc<ServerConfig>>,
+}
+
+impl TunnelProvider for LocalTlsProvider {
+    fn open(self: Box<Self>, progress: ProgressFn) -> BoxFuture<'static, Result<OpenTunnel, String>> {
+        Box::pin(async move {
+            let tls = self.tls.ok_or_else(|| "No local certificate loaded for HTTPS".to_string())?;
+            let listener = TcpListener::bind(self.addr)
+                .await
+                .map_err(|e| format!("Failed to bind {}: {}", self.addr, e))?;
+            let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
+            progress(format!("Listening locally with HTTPS on {}", local_addr));
+
+            // Handshakes run apart from the accept loop so a slow client can't hold up the others.
+            // The loop stops once the connection stream is dropped.
+            let acceptor = TlsAcceptor::from(tls);
+            let progress = Arc::new(progress);
+            let (tx, mut rx) = mpsc::channel::<Result<Box<dyn WebhookConn>, String>>(16);
+            tokio::spawn(async move {
+                loop {
+                    let accepted = tokio::select! {
+                        accepted = accept(&listener, &progress) => accepted,
+                        _ = tx.closed() => break,
+                    };
+                    let stream = match accepted {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            let _ = tx.send(Err(e)).await;
+                            break;
+                        }
+                    };
+                    let (acceptor, progress, tx) = (acceptor.clone(), progress.clone(), tx.clone());
+                    tokio::spawn(async move {
+                        match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                            Ok(Ok(conn)) => {
+                                let _ = tx.send(Ok(Box::new(conn) as Box<dyn WebhookConn>)).await;
+                            }
+                            // Usually a client that doesn't trust the local CA yet
+                            Ok(Err(e)) => progress(format!("TLS handshake failed: {}", e)),
+                            Err(_) => progress("TLS handshake timed out".to_string()),
+                        }
+                    });
+                }
+            });
+
+            let connections = futures::stream::poll_fn(move |cx| rx.poll_recv(cx)).boxed();
+            Ok(OpenTunnel { public_url: format!("https://{}", local_addr), connections, close: None })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Error, ErrorKind};
+
+    #[test]
+    fn keeps_accepting_after_per_connection_errors() {
+        assert_eq!(accept_retry_delay(&Error::from(ErrorKind::ConnectionAborted)), Some(Duration::ZERO));
+        assert_eq!(accept_retry_delay(&Error::from(ErrorKind::ConnectionReset)), Some(Duration::ZERO));
+        #[cfg(unix)]
+        assert_eq!(accept_retry_delay(&Error::from_raw_os_error(EMFILE)), Some(ACCEPT_BACKOFF));
+        assert_eq!(accept_retry_delay(&Error::from(ErrorKind::InvalidInput)), None);
+    }
+}
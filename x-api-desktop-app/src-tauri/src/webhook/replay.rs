@@ -0,0 +1,102 @@
+// Re-sends stored webhook deliveries to a target URL, e.g. to reproduce a production
+// delivery against a consumer after a fix.
+
+use super::forward::{self, ForwardConfig, ForwardResult};
+use super::service;
+use crate::event_store::StoredWebhookEvent;
+use base64::{Engine as _, engine::general_purpose};
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::{HeaderMap, Method, Uri};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use ts_rs::TS;
+
+const SIGNATURE_HEADER: &str = "x-twitter-webhooks-signature";
+
+// How long to wait between replayed deliveries
+#[derive(Deserialize, Clone, Default)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Pacing {
+    // Back to back
+    #[default]
+    None,
+    Fixed { interval_ms: u64 },
+    // The gaps between the original deliveries, divided by `speed` (2.0 replays twice as fast)
+    Original { speed: f64 },
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayArgs {
+    pub event_ids: Vec<i64>,
+    pub target_url: String,
+    // Re-sign each body with this secret so the signature matches the target's app
+    pub consumer_secret: Option<String>,
+    #[serde(default)]
+    pub pacing: Pacing,
+}
+
+#[derive(Serialize, Clone, TS)]
+pub struct ReplayOutcome {
+    #[ts(type = "number")]
+    pub event_id: i64,
+    pub result: ForwardResult,
+}
+
+fn delay_before(pacing: &Pacing, previous: Option<&StoredWebhookEvent>, event: &StoredWebhookEvent) -> Duration {
+    let Some(previous) = previous else { return Duration::ZERO };
+    match pacing {
+        Pacing::None => Duration::ZERO,
+        Pacing::Fixed { interval_ms } => Duration::from_millis(*interval_ms),
+        Pacing::Original { speed } if *speed > 0.0 => {
+            let gap_ms = event.received_at.saturating_sub(previous.received_at) as f64;
+            Duration::from_millis((gap_ms / speed) as u64)
+        }
+        Pacing::Original { .. } => Duration::ZERO,
+    }
+}
+
+// Original headers, with the signature replaced when re-signing
+fn replay_headers(event: &StoredWebhookEvent, body: &[u8], consumer_secret: Option<&str>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &event.request.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            headers.insert(name, value);
+        }
+    }
+    if let Some(secret) = consumer_secret {
+        if let Ok(signature) = HeaderValue::from_str(&service::sign(secret, body)) {
+            headers.insert(SIGNATURE_HEADER, signature);
+        }
+    }
+    headers
+}
+
+// Sends `events` in the order given, reporting each result as it completes
+pub async fn replay(
+    client: &reqwest::Client,
+    events: &[StoredWebhookEvent],
+    args: &ReplayArgs,
+    on_outcome: impl Fn(&ReplayOutcome),
+) -> Vec<ReplayOutcome> {
+    let config = ForwardConfig { url: args.target_url.clone(), return_downstream: false };
+    let mut outcomes = Vec::with_capacity(events.len());
+    let mut previous = None;
+
+    for event in events {
+        tokio::time::sleep(delay_before(&args.pacing, previous, event)).await;
+        previous = Some(event);
+
+        let body = general_purpose::STANDARD.decode(&event.request.body).unwrap_or_default();
+        let headers = replay_headers(event, &body, args.consumer_secret.as_deref());
+        let method = Method::from_bytes(event.request.method.as_bytes()).unwrap_or(Method::POST);
+        // The original path is dropped, only its query string carries over
+        let uri: Uri = event.request.uri.parse().unwrap_or_default();
+
+        let forwarded = forward::forward(client, &config, &method, &uri, &headers, body.into()).await;
+        let outcome = ReplayOutcome { event_id: event.id, result: forwarded.result };
+        on_outcome(&outcome);
+        outcomes.push(outcome);
+    }
+    outcomes
+}
@@ -0,0 +1,84 @@
+// Accept loop shared by all tunnel providers, with graceful shutdown.
+
+use super::policy::ConnectionDropped;
+use super::provider::ConnStream;
+use super::service::{self, WebhookContext};
+use super::tunnel_log::{LogLevel, LogSource};
+use crate::events::AppEvent;
+use futures::stream::StreamExt;
+use hyper::service::service_fn;
+use hyper::{Body, Request};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+// How long in-flight webhook connections get to finish when the tunnel stops
+pub const TUNNEL_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Serves webhook requests until the connection stream ends or shutdown is signalled.
+// Returns the listener error if accepting failed.
+pub async fn serve(
+    mut connections: ConnStream,
+    ctx: Arc<WebhookContext>,
+    mut shutdown_rx: watch::Receiver<bool>,
+) -> Option<String> {
+    let mut in_flight = tokio::task::JoinSet::new();
+    let mut listener_error = None;
+
+    loop {
+        // Stop accepting as soon as shutdown is requested
+        let conn_result = tokio::select! {
+            next = connections.next() => match next {
+                Some(conn_result) => conn_result,
+                None => break,
+            },
+            _ = shutdown_rx.changed() => break,
+        };
+        match conn_result {
+            Ok(conn) => {
+                let ctx = ctx.clone();
+                let mut conn_shutdown_rx = shutdown_rx.clone();
+
+                in_flight.spawn(async move {
+                    let service_ctx = ctx.clone();
+                    let service = service_fn(move |req: Request<Body>| service::handle(req, service_ctx.clone()));
+
+                    // Serve the connection, finishing the in-flight request on shutdown
+                    let connection = hyper::server::conn::Http::new().serve_connection(conn, service);
+                    tokio::pin!(connection);
+                    let result = tokio::select! {
+                        result = connection.as_mut() => result,
+                        _ = conn_shutdown_rx.changed() => {
+                            connection.as_mut().graceful_shutdown();
+                            connection.await
+                        }
+                    };
+                    match result {
+                        // Connections a response policy dropped on purpose aren't errors
+                        Err(err) if std::error::Error::source(&err).is_some_and(|e| e.is::<ConnectionDropped>()) => {}
+                        Err(err) => {
+                            let message = format!("Connection error: {}", err);
+                            ctx.log.record(LogLevel::Error, LogSource::Listener, message.clone());
+                            (ctx.sink)(AppEvent::NgrokError(ctx.message(message)));
+                        }
+                        Ok(()) => {}
+                    }
+                });
+            }
+            Err(e) => {
+                // Returned to the caller, which reports it
+                listener_error = Some(format!("Listener connection error: {}", e));
+                break;
+            }
+        }
+    }
+
+    // Drain in-flight connections, dropping any that don't finish in time
+    let drain = async { while in_flight.join_next().await.is_some() {} };
+    if tokio::time::timeout(TUNNEL_DRAIN_TIMEOUT, drain).await.is_err() {
+        ctx.log.record(LogLevel::Warn, LogSource::Listener, "Timed out draining webhook connections, aborting the rest");
+        in_flight.abort_all();
+    }
+
+    listener_error
+}
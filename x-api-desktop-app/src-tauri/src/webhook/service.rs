@@ -0,0 +1,270 @@
+// Per-request webhook handling: CRC challenges on GET, event deliveries on POST.
+// Shared by every tunnel provider.
+
+use super::forward::{self, ForwardConfig, ForwardResult};
+use super::activity_replay::ReplayJobs;
+use super::dedup::{self, DuplicateTracker};
+use super::policy::{ConnectionDropped, Policies};
+use super::tunnel_log::{LogLevel, LogSource, TunnelLog};
+use super::{activity, TunnelMessage, WebhookRequestInfo};
+use crate::events::{AppEvent, EventSink};
+use base64::{Engine as _, engine::general_purpose};
+use hmac::{Hmac, Mac};
+use hyper::header::HeaderValue;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Unauthenticated liveness check, answered without touching any endpoint
+pub const HEALTH_PATH: &str = "/health";
+
+// Endpoints with their own settings are served at /webhooks/{id}
+const ENDPOINT_PATH_PREFIX: &str = "/webhooks/";
+
+// How one webhook endpoint verifies and answers requests
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointConfig {
+    pub consumer_secret: String,
+    // Answer deliveries without a valid x-twitter-webhooks-signature with 401
+    #[serde(default)]
+    pub reject_invalid_signatures: bool,
+    // Relay every request to a local development server
+    pub forward: Option<ForwardConfig>,
+}
+
+// What a running webhook listener needs to answer requests
+pub struct WebhookContext {
+    pub tunnel_id: String, // Set on every event the listener emits
+    pub endpoint: EndpointConfig, // Answers every path without an endpoint of its own
+    pub endpoints: HashMap<String, EndpointConfig>, // Keyed by the {id} in /webhooks/{id}
+    pub policies: Policies, // Simulated failures, changeable while running
+    pub client: reqwest::Client,
+    pub sink: EventSink,
+    pub log: TunnelLog, // The tunnel's console log
+    pub replays: Arc<ReplayJobs>, // For tagging deliveries that replay jobs produce
+    pub duplicates: DuplicateTracker, // Flags and counts redeliveries of the same event
+}
+
+impl WebhookContext {
+    pub fn message(&self, message: String) -> TunnelMessage {
+        TunnelMessage { tunnel_id: self.tunnel_id.clone(), message }
+    }
+
+    // The endpoint serving `path` and its id. Paths outside /webhooks/{id} go to the default
+    // endpoint; an id that isn't configured gets None rather than the default's secret.
+    fn route(&self, path: &str) -> Option<(Option<&str>, &EndpointConfig)> {
+        let id = path.strip_prefix(ENDPOINT_PATH_PREFIX).map(|rest| rest.trim_end_matches('/'));
+        match id.filter(|id| !id.is_empty()) {
+            Some(id) => self.endpoints.get_key_value(id).map(|(id, endpoint)| (Some(id.as_str()), endpoint)),
+            None => Some((None, &self.endpoint)),
+        }
+    }
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn mac_for(consumer_secret: &str, data: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(consumer_secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(data);
+    mac
+}
+
+// `sha256=<base64 HMAC-SHA256 of data keyed by the consumer secret>`, the format X uses for
+// both CRC responses and delivery signatures
+pub fn sign(consumer_secret: &str, data: &[u8]) -> String {
+    let code_bytes = mac_for(consumer_secret, data).finalize().into_bytes();
+    format!("sha256={}", general_purpose::STANDARD.encode(code_bytes))
+}
+
+pub fn crc_response_token(consumer_secret: &str, crc_token: &str) -> String {
+    sign(consumer_secret, crc_token.as_bytes())
+}
+
+// Checks an x-twitter-webhooks-signature header against the raw body in constant time
+pub fn verify_signature(consumer_secret: &str, body: &[u8], header: Option<&str>) -> bool {
+    let Some(encoded) = header.and_then(|h| h.trim().strip_prefix("sha256=")) else {
+        return false;
+    };
+    let Ok(expected) = general_purpose::STANDARD.decode(encoded) else {
+        return false;
+    };
+    mac_for(consumer_secret, body).verify_slice(&expected).is_ok()
+}
+
+fn empty_response(status: StatusCode) -> Response<Body> {
+    let mut resp = Response::new(Body::empty());
+    *resp.status_mut() = status;
+    resp
+}
+
+fn health_response() -> Response<Body> {
+    let mut resp = Response::new(Body::from(r#"{"status":"ok"}"#));
+    resp.headers_mut().insert(hyper::header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    resp
+}
+
+async fn handle_crc(req: Request<Body>, ctx: &WebhookContext, endpoint: &EndpointConfig, wrong_crc: bool) -> Response<Body> {
+    if let Some(config) = &endpoint.forward {
+        let forwarded = forward::forward(&ctx.client, config, req.method(), req.uri(), req.headers(), Default::default()).await;
+        let status = forwarded.result.status.map_or_else(|| "no response".to_string(), |status| status.to_string());
+        ctx.log.record(LogLevel::Info, LogSource::Crc, format!("Forwarded CRC check to {}: {}", forwarded.result.url, status));
+        if config.return_downstream {
+            return forwarded.into_response();
+        }
+    }
+
+    let query_params: HashMap<String, String> = req.uri().query()
+        .map(|v| url::form_urlencoded::parse(v.as_bytes()).into_owned().collect())
+        .unwrap_or_default();
+
+    let Some(crc_token) = query_params.get("crc_token") else {
+        ctx.log.record(LogLevel::Warn, LogSource::Crc, "GET request received without crc_token");
+        let mut resp = Response::new(Body::from("Missing crc_token parameter"));
+        *resp.status_mut() = StatusCode::BAD_REQUEST;
+        return resp;
+    };
+
+    ctx.log.record(LogLevel::Info, LogSource::Crc, format!("Received CRC check with token: {}", crc_token));
+    let mut response_token = crc_response_token(&endpoint.consumer_secret, crc_token);
+    if wrong_crc {
+        // Same format, different HMAC, as if the consumer secret were wrong
+        response_token = crc_response_token(&endpoint.consumer_secret, &format!("{}-wrong", crc_token));
+    }
+    ctx.log.record(LogLevel::Debug, LogSource::Crc, format!("Generated CRC response: {}", response_token));
+
+    let json_response = serde_json::json!({
+        "response_token": response_token
+    });
+
+    match Response::builder()
+        .status(StatusCode::OK)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json_response.to_string()))
+    {
+        Ok(resp) => resp,
+        Err(e) => {
+            ctx.log.record(LogLevel::Error, LogSource::Crc, format!("Failed to build CRC response: {}", e));
+            let mut resp = Response::new(Body::from("Internal Server Error"));
+            *resp.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            resp
+        }
+    }
+}
+
+async fn handle_delivery(
+    req: Request<Body>,
+    ctx: &WebhookContext,
+    endpoint_id: Option<&str>,
+    endpoint: &EndpointConfig,
+) -> Response<Body> {
+    ctx.log.record(LogLevel::Info, LogSource::Delivery, format!("Received webhook delivery at {}", req.uri().path()));
+
+    // --- Capture Request Details ---
+    let (parts, body) = req.into_parts();
+    let signature = parts.headers
+        .get("x-twitter-webhooks-signature")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let headers = parts.headers.iter().map(|(k, v)| {
+        (k.to_string(), v.to_str().unwrap_or("[invalid header value]").to_string())
+    }).collect();
+
+    let body_bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            ctx.log.record(LogLevel::Error, LogSource::Delivery, format!("Failed to read POST request body: {}", e));
+            let mut response = Response::new(Body::from(format!("Error reading body: {}", e)));
+            *response.status_mut() = StatusCode::BAD_REQUEST;
+            return response;
+        }
+    };
+
+    let signature_valid = verify_signature(&endpoint.consumer_secret, &body_bytes, signature.as_deref());
+    if !signature_valid {
+        ctx.log.record(LogLevel::Warn, LogSource::Delivery, "Webhook delivery has a missing or invalid signature");
+    }
+
+    let rejected = !signature_valid && endpoint.reject_invalid_signatures;
+
+    // Rejected deliveries don't count, so a genuine one arriving after a forged copy isn't a repeat
+    let fingerprint = dedup::fingerprint(&body_bytes);
+    let duplicate = !rejected && ctx.duplicates.record(&fingerprint);
+    if duplicate {
+        ctx.log.record(LogLevel::Info, LogSource::Delivery, format!("Duplicate delivery ({})", fingerprint));
+    }
+
+    // Rejected deliveries aren't relayed downstream
+    let forwarded = match &endpoint.forward {
+        Some(config) if !rejected => Some(
+            forward::forward(&ctx.client, config, &parts.method, &parts.uri, &parts.headers, body_bytes.clone()).await,
+        ),
+        _ => None,
+    };
+    if let Some(ForwardResult { url, error: Some(error), .. }) = forwarded.as_ref().map(|f| &f.result) {
+        ctx.log.record(LogLevel::Warn, LogSource::Delivery, format!("Forwarding to {} failed: {}", url, error));
+    }
+
+    // --- Create Payload & Emit Event ---
+    // Rejected deliveries are still shown so a bad signature can be debugged
+    let payload = WebhookRequestInfo {
+        tunnel_id: ctx.tunnel_id.clone(),
+        endpoint_id: endpoint_id.map(str::to_string),
+        method: parts.method.to_string(),
+        uri: parts.uri.to_string(),
+        headers,
+        body: general_purpose::STANDARD.encode(&body_bytes),
+        signature_valid,
+        activity: activity::summarize(&body_bytes),
+        forward: forwarded.as_ref().map(|f| f.result.clone()),
+        // Unsigned deliveries could otherwise end a replay job or inflate its count
+        replay_job_id: if signature_valid { ctx.replays.classify(&ctx.tunnel_id, &body_bytes) } else { None },
+        fingerprint: Some(fingerprint),
+        duplicate,
+    };
+    (ctx.sink)(AppEvent::NgrokWebhook(Box::new(payload)));
+
+    if rejected {
+        return empty_response(StatusCode::UNAUTHORIZED);
+    }
+    match forwarded {
+        Some(forwarded) if endpoint.forward.as_ref().is_some_and(|c| c.return_downstream) => forwarded.into_response(),
+        _ => empty_response(StatusCode::OK),
+    }
+}
+
+pub async fn handle(req: Request<Body>, ctx: Arc<WebhookContext>) -> Result<Response<Body>, ConnectionDropped> {
+    ctx.log.record(LogLevel::Debug, LogSource::Listener, format!("Handling request: {} {}", req.method(), req.uri()));
+
+    if req.uri().path() == HEALTH_PATH {
+        return Ok(health_response());
+    }
+
+    let path = req.uri().path().to_string();
+    let Some((endpoint_id, endpoint)) = ctx.route(&path) else {
+        ctx.log.record(LogLevel::Warn, LogSource::Listener, format!("No endpoint configured for {}", path));
+        return Ok(empty_response(StatusCode::NOT_FOUND));
+    };
+    let decision = ctx.policies.decide(endpoint_id, req.method() == Method::POST);
+    let response = match *req.method() {
+        Method::GET => handle_crc(req, &ctx, endpoint, decision.wrong_crc).await,
+        Method::POST => handle_delivery(req, &ctx, endpoint_id, endpoint).await,
+        ref method => {
+            ctx.log.record(LogLevel::Warn, LogSource::Listener, format!("Received {} request - Method Not Allowed", method));
+            empty_response(StatusCode::METHOD_NOT_ALLOWED)
+        }
+    };
+
+    // The request is handled (and shown) as usual; the policy only changes what X gets back
+    if !decision.delay.is_zero() {
+        tokio::time::sleep(decision.delay).await;
+    }
+    if decision.drop_connection {
+        ctx.log.record(LogLevel::Info, LogSource::Listener, "Dropping connection per response policy");
+        return Err(ConnectionDropped);
+    }
+    Ok(decision.status.map_or(response, empty_response))
+}
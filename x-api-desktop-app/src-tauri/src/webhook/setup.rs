@@ -0,0 +1,399 @@
+// One-shot Account Activity setup and teardown: start the tunnel, register it with
+// POST /2/webhooks, wait for X to validate the CRC and subscribe the app owner. If a step fails,
+// the steps already done are undone in reverse order.
+
+use super::activity_replay::format_replay_date;
+use crate::oauth1::{self, OAuth1Keys};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+pub const DEFAULT_CRC_TIMEOUT: Duration = Duration::from_secs(30);
+const CRC_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Serialize, PartialEq, Debug, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum SetupStep {
+    StartTunnel,
+    RegisterWebhook,
+    ValidateCrc,
+    Subscribe,
+    Unsubscribe,
+    DeleteWebhook,
+    StopTunnel,
+}
+
+#[derive(Clone, Copy, Serialize, PartialEq, Debug, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Started,
+    Done,
+    Failed,
+    RolledBack, // Undone after a later step failed
+    Skipped,
+}
+
+// Payload for the `webhook://setup-progress` event
+#[derive(Clone, Serialize, TS)]
+pub struct SetupProgress {
+    pub tunnel_id: String,
+    pub step: SetupStep,
+    pub status: StepStatus,
+    pub message: Option<String>,
+}
+
+pub type StepFn<'a> = &'a (dyn Fn(SetupStep, StepStatus, Option<String>) + Send + Sync);
+
+// What setup created, which teardown removes again
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookSetup {
+    pub tunnel_id: String,
+    pub webhook_id: String,
+    pub webhook_url: String,
+    pub user_id: Option<String>, // The subscribed user; unknown if the access token doesn't say
+}
+
+// Starts and stops the tunnel the webhook is registered for
+pub trait TunnelControl: Send + Sync {
+    // Resolves to the tunnel's public URL
+    fn start(&self) -> BoxFuture<'_, Result<String, String>>;
+    fn stop(&self) -> BoxFuture<'_, Result<(), String>>;
+}
+
+enum Auth {
+    Bearer, // App-only
+    OAuth1, // The app owner's user context
+}
+
+// The X API calls for managing a webhook
+pub struct XApi {
+    pub client: reqwest::Client,
+    pub base_url: String, // e.g. "https://api.x.com"
+    pub headers: HashMap<String, String>, // From the environment profile
+    pub bearer_token: String,
+    pub oauth1_keys: Option<OAuth1Keys>, // Needed to subscribe
+}
+
+impl XApi {
+    async fn send(&self, method: reqwest::Method, path: &str, auth: Auth) -> Result<Value, String> {
+        self.send_json(method, path, auth, None).await
+    }
+
+    async fn send_json(&self, method: reqwest::Method, path: &str, auth: Auth, body: Option<Value>) -> Result<Value, String> {
+        let url = format!("{}{}", self.base_url.trim_end_matches('/'), path);
+        let mut builder = self.client.request(method.clone(), &url);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        builder = match auth {
+            Auth::Bearer => builder.bearer_auth(&self.bearer_token),
+            Auth::OAuth1 => {
+                let keys = self.oauth1_keys.as_ref().ok_or_else(|| "OAuth 1.0a keys are required to subscribe".to_string())?;
+                builder.header(reqwest::header::AUTHORIZATION, oauth1::authorization_header(method.as_str(), &url, keys, &[])?)
+            }
+        };
+        if let Some(body) = body {
+            builder = builder.json(&body);
+        }
+
+        let response = builder.send().await.map_err(|e| format!("{} {} failed: {}", method, path, e))?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| format!("Failed to read {} {} response: {}", method, path, e))?;
+        if !status.is_success() {
+            return Err(format!("{} {} failed with status {}: {}", method, path, status.as_u16(), text));
+        }
+        if text.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        serde_json::from_str(&text).map_err(|e| format!("Unexpected {} {} response: {}", method, path, e))
+    }
+
+    // Registers `url` and returns the new webhook id; X sends the first CRC check while this runs
+    pub async fn create_webhook(&self, url: &str) -> Result<String, String> {
+        let body = serde_json::json!({ "url": url });
+        let response = self.send_json(reqwest::Method::POST, "/2/webhooks", Auth::Bearer, Some(body)).await?;
+        response["data"]["id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("POST /2/webhooks returned no webhook id: {}", response))
+    }
+
+    // Whether X currently considers the webhook valid; None if it isn't registered
+    pub async fn webhook_valid(&self, webhook_id: &str) -> Result<Option<bool>, String> {
+        let response = self.send(reqwest::Method::GET, "/2/webhooks", Auth::Bearer).await?;
+        let webhooks = response["data"].as_array().cloned().unwrap_or_default();
+        Ok(webhooks
+            .iter()
+            .find(|webhook| webhook["id"].as_str() == Some(webhook_id))
+            .map(|webhook| webhook["valid"].as_bool().unwrap_or(false)))
+    }
+
+    pub async fn delete_webhook(&self, webhook_id: &str) -> Result<(), String> {
+        self.send(reqwest::Method::DELETE, &format!("/2/webhooks/{}", webhook_id), Auth::Bearer).await.map(|_| ())
+    }
+
+    // Subscribes the user whose OAuth1 access token signs the request
+    pub async fn subscribe(&self, webhook_id: &str) -> Result<(), String> {
+        let path = format!("/2/account_activity/webhooks/{}/subscriptions/all", webhook_id);
+        self.send(reqwest::Method::POST, &path, Auth::OAuth1).await.map(|_| ())
+    }
+
+    pub async fn unsubscribe(&self, webhook_id: &str, user_id: &str) -> Result<(), String> {
+        let path = format!("/2/account_activity/webhooks/{}/subscriptions/{}/all", webhook_id, user_id);
+        self.send(reqwest::Method::DELETE, &path, Auth::Bearer).await.map(|_| ())
+    }
+
+    // Asks X to redeliver the webhook's events between `from` and `to` (ms since the Unix epoch);
+    // returns the replay job id
+    pub async fn request_replay(&self, webhook_id: &str, from: u64, to: u64) -> Result<String, String> {
+        let path = format!(
+            "/2/account_activity/replay/webhooks/{}/subscriptions/all?from_date={}&to_date={}",
+            webhook_id,
+            format_replay_date(from),
+            format_replay_date(to)
+        );
+        let response = self.send(reqwest::Method::POST, &path, Auth::Bearer).await?;
+        response["data"]["job_id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("Replay request returned no job id: {}", response))
+    }
+
+    // User access tokens start with the user's id, e.g. "1234567890-AbCd..."
+    fn subscribed_user_id(&self) -> Option<String> {
+        let token = &self.oauth1_keys.as_ref()?.access_token;
+        let (user_id, _) = token.split_once('-')?;
+        (!user_id.is_empty() && user_id.chars().all(|c| c.is_ascii_digit())).then(|| user_id.to_string())
+    }
+}
+
+// Reports a step as started, then done or failed
+async fn run_step<T>(step: SetupStep, progress: StepFn<'_>, work: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+    progress(step, StepStatus::Started, None);
+    match work.await {
+        Ok(value) => {
+            progress(step, StepStatus::Done, None);
+            Ok(value)
+        }
+        Err(e) => {
+            progress(step, StepStatus::Failed, Some(e.clone()));
+            Err(e)
+        }
+    }
+}
+
+// Polls until X marks the webhook valid, i.e. our CRC response was accepted
+async fn wait_for_crc(api: &XApi, webhook_id: &str, timeout: Duration) -> Result<(), String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match api.webhook_valid(webhook_id).await? {
+            Some(true) => return Ok(()),
+            Some(false) => {}
+            None => return Err(format!("Webhook {} is no longer registered", webhook_id)),
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(format!("X did not validate the webhook's CRC response within {} s", timeout.as_secs()));
+        }
+        tokio::time::sleep(CRC_POLL_INTERVAL.min(remaining)).await;
+    }
+}
+
+async fn register_and_subscribe(
+    api: &XApi,
+    webhook_url: &str,
+    crc_timeout: Duration,
+    progress: StepFn<'_>,
+    webhook_id: &mut Option<String>,
+) -> Result<(), String> {
+    let id = run_step(SetupStep::RegisterWebhook, progress, api.create_webhook(webhook_url)).await?;
+    *webhook_id = Some(id.clone());
+    run_step(SetupStep::ValidateCrc, progress, wait_for_crc(api, &id, crc_timeout)).await?;
+    run_step(SetupStep::Subscribe, progress, api.subscribe(&id)).await
+}
+
+// Undoes a partial setup; failures here are only reported
+async fn roll_back(api: &XApi, tunnel: &dyn TunnelControl, webhook_id: Option<&str>, progress: StepFn<'_>) {
+    if let Some(webhook_id) = webhook_id {
+        match api.delete_webhook(webhook_id).await {
+            Ok(()) => progress(SetupStep::DeleteWebhook, StepStatus::RolledBack, None),
+            Err(e) => progress(SetupStep::DeleteWebhook, StepStatus::Failed, Some(e)),
+        }
+    }
+    match tunnel.stop().await {
+        Ok(()) => progress(SetupStep::StopTunnel, StepStatus::RolledBack, None),
+        Err(e) => progress(SetupStep::StopTunnel, StepStatus::Failed, Some(e)),
+    }
+}
+
+// Runs the whole setup; `webhook_path` is appended to the tunnel's public URL, e.g. "/webhook"
+pub async fn setup(
+    api: &XApi,
+    tunnel: &dyn TunnelControl,
+    tunnel_id: &str,
+    webhook_path: &str,
+    crc_timeout: Duration,
+    progress: StepFn<'_>,
+) -> Result<WebhookSetup, String> {
+    let public_url = run_step(SetupStep::StartTunnel, progress, tunnel.start()).await?;
+    let webhook_url = format!("{}{}", public_url.trim_end_matches('/'), webhook_path);
+
+    let mut webhook_id = None;
+    if let Err(e) = register_and_subscribe(api, &webhook_url, crc_timeout, progress, &mut webhook_id).await {
+        roll_back(api, tunnel, webhook_id.as_deref(), progress).await;
+        return Err(e);
+    }
+    Ok(WebhookSetup {
+        tunnel_id: tunnel_id.to_string(),
+        webhook_id: webhook_id.unwrap_or_default(),
+        webhook_url,
+        user_id: api.subscribed_user_id(),
+    })
+}
+
+// Unsubscribes, deletes the webhook and stops the tunnel, carrying on past failures
+pub async fn teardown(api: &XApi, tunnel: &dyn TunnelControl, setup: &WebhookSetup, progress: StepFn<'_>) -> Result<(), String> {
+    let mut errors = Vec::new();
+    match &setup.user_id {
+        Some(user_id) => {
+            if let Err(e) = run_step(SetupStep::Unsubscribe, progress, api.unsubscribe(&setup.webhook_id, user_id)).await {
+                errors.push(e);
+            }
+        }
+        None => progress(SetupStep::Unsubscribe, StepStatus::Skipped, Some("The subscribed user is unknown".to_string())),
+    }
+    if let Err(e) = run_step(SetupStep::DeleteWebhook, progress, api.delete_webhook(&setup.webhook_id)).await {
+        errors.push(e);
+    }
+    if let Err(e) = run_step(SetupStep::StopTunnel, progress, tunnel.stop()).await {
+        errors.push(e);
+    }
+    if errors.is_empty() { Ok(()) } else { Err(errors.join("; ")) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    // Minimal stand-in for the X API. Records "METHOD path auth" per request.
+    async fn start_mock_api(fail_subscribe: bool) -> (String, Arc<Mutex<Vec<String>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let server_requests = requests.clone();
+        let make_svc = make_service_fn(move |_| {
+            let requests = server_requests.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let requests = requests.clone();
+                    async move {
+                        let auth = req.headers().get("authorization").and_then(|v| v.to_str().ok()).unwrap_or("");
+                        let auth = auth.split(' ').next().unwrap_or("").to_string();
+                        let line = format!("{} {} {}", req.method(), req.uri().path(), auth);
+                        requests.lock().unwrap().push(line.clone());
+                        let (status, body) = match line.as_str() {
+                            "POST /2/webhooks Bearer" => (200, r#"{"data":{"id":"77","url":"u","valid":true}}"#),
+                            "GET /2/webhooks Bearer" => (200, r#"{"data":[{"id":"77","valid":true}]}"#),
+                            "POST /2/account_activity/webhooks/77/subscriptions/all OAuth" if fail_subscribe => {
+                                (403, r#"{"title":"Forbidden"}"#)
+                            }
+                            "POST /2/account_activity/webhooks/77/subscriptions/all OAuth" => (200, r#"{"data":{"subscribed":true}}"#),
+                            "DELETE /2/account_activity/webhooks/77/subscriptions/1234/all Bearer" => (200, r#"{"data":{"subscribed":false}}"#),
+                            "DELETE /2/webhooks/77 Bearer" => (200, r#"{"data":{"deleted":true}}"#),
+                            _ => (404, ""),
+                        };
+                        Ok::<_, Infallible>(Response::builder().status(status).body(Body::from(body)).unwrap())
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        (url, requests)
+    }
+
+    #[derive(Default)]
+    struct FakeTunnel {
+        calls: Mutex<Vec<&'static str>>,
+    }
+
+    impl TunnelControl for FakeTunnel {
+        fn start(&self) -> BoxFuture<'_, Result<String, String>> {
+            self.calls.lock().unwrap().push("start");
+            Box::pin(async { Ok("https://example.ngrok.app/".to_string()) })
+        }
+
+        fn stop(&self) -> BoxFuture<'_, Result<(), String>> {
+            self.calls.lock().unwrap().push("stop");
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    fn api(base_url: String) -> XApi {
+        XApi {
+            client: reqwest::Client::new(),
+            base_url,
+            headers: HashMap::new(),
+            bearer_token: "app-token".to_string(),
+            oauth1_keys: Some(OAuth1Keys {
+                api_key: "key".to_string(),
+                api_secret: "secret".to_string(),
+                access_token: "1234-token".to_string(),
+                access_secret: "token-secret".to_string(),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn sets_up_and_tears_down_in_order() {
+        let (base_url, requests) = start_mock_api(false).await;
+        let api = api(base_url);
+        let tunnel = FakeTunnel::default();
+        let steps = Mutex::new(Vec::new());
+        let progress = |step, status, _| steps.lock().unwrap().push((step, status));
+
+        let setup = setup(&api, &tunnel, "prod", "/webhook", DEFAULT_CRC_TIMEOUT, &progress).await.unwrap();
+        assert_eq!(setup.webhook_url, "https://example.ngrok.app/webhook");
+        assert_eq!((setup.webhook_id.as_str(), setup.user_id.as_deref()), ("77", Some("1234")));
+        teardown(&api, &tunnel, &setup, &progress).await.unwrap();
+
+        assert_eq!(*requests.lock().unwrap(), [
+            "POST /2/webhooks Bearer",
+            "GET /2/webhooks Bearer",
+            "POST /2/account_activity/webhooks/77/subscriptions/all OAuth",
+            "DELETE /2/account_activity/webhooks/77/subscriptions/1234/all Bearer",
+            "DELETE /2/webhooks/77 Bearer",
+        ]);
+        assert_eq!(*tunnel.calls.lock().unwrap(), ["start", "stop"]);
+        let done: Vec<SetupStep> = steps.lock().unwrap().iter().filter(|(_, s)| *s == StepStatus::Done).map(|(step, _)| *step).collect();
+        assert_eq!(done.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn rolls_back_when_subscribing_fails() {
+        let (base_url, requests) = start_mock_api(true).await;
+        let api = api(base_url);
+        let tunnel = FakeTunnel::default();
+        let steps = Mutex::new(Vec::new());
+        let progress = |step, status, _| steps.lock().unwrap().push((step, status));
+
+        let error = setup(&api, &tunnel, "prod", "/webhook", DEFAULT_CRC_TIMEOUT, &progress).await.unwrap_err();
+        assert!(error.contains("403"), "{}", error);
+        assert_eq!(requests.lock().unwrap().last().map(String::as_str), Some("DELETE /2/webhooks/77 Bearer"));
+        assert_eq!(*tunnel.calls.lock().unwrap(), ["start", "stop"]);
+        let steps = steps.lock().unwrap();
+        assert!(steps.contains(&(SetupStep::Subscribe, StepStatus::Failed)));
+        assert_eq!(steps[steps.len() - 2..], [
+            (SetupStep::DeleteWebhook, StepStatus::RolledBack),
+            (SetupStep::StopTunnel, StepStatus::RolledBack),
+        ]);
+    }
+}
@@ -0,0 +1,129 @@
+// Keeps a tunnel up: serves it, probes it with a CRC check through its public URL, and opens a
+// new session with backoff whenever the listener dies.
+
+use super::provider::{OpenTunnel, ProviderConfig};
+use super::service::WebhookContext;
+use super::tunnel_log::{LogLevel, LogSource};
+use super::{crc_check, server};
+use futures::future::OptionFuture;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use ts_rs::TS;
+
+pub const DEFAULT_PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+pub struct SupervisorConfig {
+    pub probe_interval: Option<Duration>, // None disables the self-CRC probe
+    pub backoff_initial: Duration,
+    pub backoff_max: Duration,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        SupervisorConfig {
+            probe_interval: Some(DEFAULT_PROBE_INTERVAL),
+            backoff_initial: Duration::from_secs(1),
+            backoff_max: Duration::from_secs(60),
+        }
+    }
+}
+
+// Outcome of one self-CRC probe through the public URL
+#[derive(Serialize, Clone, TS)]
+pub struct ProbeResult {
+    #[ts(type = "number")]
+    pub at: u64, // Milliseconds since the Unix epoch
+    pub valid: bool,
+    #[ts(type = "number")]
+    pub latency_ms: u64,
+    pub problems: Vec<String>,
+}
+
+pub enum SupervisorUpdate {
+    Progress(String), // From the provider while reconnecting
+    Disconnected(String), // Why the listener stopped
+    Reconnecting { attempt: u32, delay: Duration },
+    ReconnectFailed(String),
+    Reconnected { previous_url: String, url: String },
+    Probe(ProbeResult),
+}
+
+pub type UpdateFn = Arc<dyn Fn(SupervisorUpdate) + Send + Sync>;
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+// Probes the public URL on every interval tick; never returns
+async fn probe_loop(public_url: &str, ctx: &WebhookContext, interval: Duration, on_update: &UpdateFn) {
+    let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    loop {
+        ticks.tick().await;
+        let result = match crc_check::check(&ctx.client, public_url, &ctx.endpoint.consumer_secret).await {
+            Ok(report) => ProbeResult { at: now_millis(), valid: report.valid, latency_ms: report.latency_ms, problems: report.problems },
+            Err(e) => ProbeResult { at: now_millis(), valid: false, latency_ms: 0, problems: vec![e] },
+        };
+        on_update(SupervisorUpdate::Probe(result));
+    }
+}
+
+// Opens a new session, backing off between failed attempts; None once shutdown is signalled
+async fn reconnect(
+    provider: &ProviderConfig,
+    config: &SupervisorConfig,
+    shutdown_rx: &mut watch::Receiver<bool>,
+    on_update: &UpdateFn,
+) -> Option<OpenTunnel> {
+    let mut delay = config.backoff_initial;
+    for attempt in 1.. {
+        on_update(SupervisorUpdate::Reconnecting { attempt, delay });
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = shutdown_rx.changed() => return None,
+        }
+        let progress_update = on_update.clone();
+        let progress = Box::new(move |message: String| progress_update(SupervisorUpdate::Progress(message)));
+        match provider.clone().into_provider().open(progress).await {
+            Ok(tunnel) => return Some(tunnel),
+            Err(e) => on_update(SupervisorUpdate::ReconnectFailed(e)),
+        }
+        delay = (delay * 2).min(config.backoff_max);
+    }
+    None
+}
+
+// Runs until shutdown is signalled, reopening the tunnel from `provider` whenever it drops
+pub async fn supervise(
+    provider: ProviderConfig,
+    mut tunnel: OpenTunnel,
+    ctx: Arc<WebhookContext>,
+    config: SupervisorConfig,
+    mut shutdown_rx: watch::Receiver<bool>,
+    on_update: UpdateFn,
+) {
+    loop {
+        let OpenTunnel { public_url, connections, close } = tunnel;
+        let serving = server::serve(connections, ctx.clone(), shutdown_rx.clone());
+        let error = match config.probe_interval {
+            Some(interval) => tokio::select! {
+                error = serving => error,
+                _ = probe_loop(&public_url, &ctx, interval, &on_update) => None,
+            },
+            None => serving.await,
+        };
+        if let Some(Err(e)) = OptionFuture::from(close).await {
+            ctx.log.record(LogLevel::Error, LogSource::Session, e);
+        }
+        if *shutdown_rx.borrow() {
+            return;
+        }
+
+        on_update(SupervisorUpdate::Disconnected(error.unwrap_or_else(|| "Tunnel connection closed".to_string())));
+        let Some(next) = reconnect(&provider, &config, &mut shutdown_rx, &on_update).await else { return };
+        on_update(SupervisorUpdate::Reconnected { previous_url: public_url, url: next.public_url.clone() });
+        tunnel = next;
+    }
+}
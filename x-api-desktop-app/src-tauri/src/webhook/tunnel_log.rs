@@ -0,0 +1,196 @@
+// Per-tunnel console log: a bounded ring buffer of structured entries, so a tunnel left open
+// for days keeps only its most recent history.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Entries kept per tunnel; older ones are evicted first
+pub const DEFAULT_CAPACITY: usize = 2000;
+
+// Longer messages are cut so one entry can't hold a whole payload
+const MAX_MESSAGE_LEN: usize = 2000;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+// What part of the tunnel an entry came from
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum LogSource {
+    Session, // Opening, closing and reconnecting the provider session
+    Listener, // Accepting and serving connections
+    Crc, // CRC challenges and self-CRC probes
+    Delivery, // Webhook deliveries
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LogEntry {
+    pub seq: u64, // Increases by one per entry, including evicted ones
+    pub at: u64, // Milliseconds since the Unix epoch
+    pub level: LogLevel,
+    pub source: LogSource,
+    pub message: String,
+}
+
+// Which entries to fetch or export
+#[derive(Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogQuery {
+    pub after_seq: Option<u64>, // Only entries newer than this, for paging or polling
+    pub limit: Option<usize>, // At most this many, oldest first
+    pub min_level: Option<LogLevel>,
+    pub source: Option<LogSource>,
+}
+
+impl LogQuery {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        self.after_seq.is_none_or(|seq| entry.seq > seq)
+            && self.min_level.is_none_or(|level| entry.level >= level)
+            && self.source.is_none_or(|source| entry.source == source)
+    }
+}
+
+#[derive(Serialize)]
+pub struct LogPage {
+    pub entries: Vec<LogEntry>,
+    pub evicted: u64, // Entries dropped so far to stay within capacity
+    pub next_seq: u64, // Sequence number the next entry will get
+}
+
+pub struct LogBuffer {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+    next_seq: u64,
+}
+
+impl LogBuffer {
+    pub fn with_capacity(capacity: usize) -> Self {
+        LogBuffer { entries: VecDeque::with_capacity(capacity.min(64)), capacity: capacity.max(1), next_seq: 0 }
+    }
+
+    pub fn push(&mut self, level: LogLevel, source: LogSource, mut message: String) {
+        if message.len() > MAX_MESSAGE_LEN {
+            let mut end = MAX_MESSAGE_LEN;
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            message.truncate(end);
+            message.push('…');
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        let at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        self.entries.push_back(LogEntry { seq: self.next_seq, at, level, source, message });
+        self.next_seq += 1;
+    }
+
+    pub fn query(&self, query: &LogQuery) -> LogPage {
+        let matching = self.entries.iter().filter(|entry| query.matches(entry)).cloned();
+        LogPage {
+            entries: matching.take(query.limit.unwrap_or(usize::MAX)).collect(),
+            evicted: self.next_seq - self.entries.len() as u64,
+            next_seq: self.next_seq,
+        }
+    }
+}
+
+// Shared handle to one tunnel's log, held by the tunnel's status and its listener
+#[derive(Clone)]
+pub struct TunnelLog(Arc<Mutex<LogBuffer>>);
+
+impl Default for TunnelLog {
+    fn default() -> Self {
+        TunnelLog(Arc::new(Mutex::new(LogBuffer::with_capacity(DEFAULT_CAPACITY))))
+    }
+}
+
+impl TunnelLog {
+    pub fn record(&self, level: LogLevel, source: LogSource, message: impl Into<String>) {
+        if let Ok(mut buffer) = self.0.lock() {
+            buffer.push(level, source, message.into());
+        }
+    }
+
+    pub fn query(&self, query: &LogQuery) -> Result<LogPage, String> {
+        let buffer = self.0.lock().map_err(|e| format!("Mutex lock error: {}", e))?;
+        Ok(buffer.query(query))
+    }
+
+    // Writes the matching entries to `path` as JSON Lines; returns how many were written
+    pub fn export(&self, path: &Path, query: &LogQuery) -> Result<usize, String> {
+        let page = self.query(query)?;
+        let mut file = std::fs::File::create(path)
+            .map(std::io::BufWriter::new)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        for entry in &page.entries {
+            let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+            writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        file.flush().map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(page.entries.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_oldest_entries_past_capacity() {
+        let mut buffer = LogBuffer::with_capacity(3);
+        for i in 0..5 {
+            buffer.push(LogLevel::Info, LogSource::Listener, format!("entry {}", i));
+        }
+        buffer.push(LogLevel::Info, LogSource::Delivery, "x".repeat(MAX_MESSAGE_LEN * 2));
+
+        let page = buffer.query(&LogQuery::default());
+        let seqs: Vec<u64> = page.entries.iter().map(|entry| entry.seq).collect();
+        assert_eq!(seqs, vec![3, 4, 5]);
+        assert_eq!((page.evicted, page.next_seq), (3, 6));
+        assert!(page.entries[2].message.len() <= MAX_MESSAGE_LEN + '…'.len_utf8());
+    }
+
+    #[test]
+    fn filters_by_level_source_and_range() {
+        let log = TunnelLog::default();
+        log.record(LogLevel::Info, LogSource::Session, "Tunnel established");
+        log.record(LogLevel::Warn, LogSource::Crc, "Self CRC check failed");
+        log.record(LogLevel::Error, LogSource::Listener, "Listener connection error");
+        log.record(LogLevel::Debug, LogSource::Crc, "CRC check answered");
+
+        let messages = |query: LogQuery| -> Vec<String> {
+            log.query(&query).unwrap().entries.into_iter().map(|entry| entry.message).collect()
+        };
+        assert_eq!(
+            messages(LogQuery { min_level: Some(LogLevel::Warn), ..Default::default() }),
+            vec!["Self CRC check failed", "Listener connection error"]
+        );
+        assert_eq!(
+            messages(LogQuery { source: Some(LogSource::Crc), ..Default::default() }),
+            vec!["Self CRC check failed", "CRC check answered"]
+        );
+        assert_eq!(
+            messages(LogQuery { after_seq: Some(0), limit: Some(2), ..Default::default() }),
+            vec!["Self CRC check failed", "Listener connection error"]
+        );
+
+        let path = std::env::temp_dir().join(format!("tunnel-log-{}.jsonl", std::process::id()));
+        let query = LogQuery { min_level: Some(LogLevel::Error), ..Default::default() };
+        assert_eq!(log.export(&path, &query).unwrap(), 1);
+        let exported = std::fs::read_to_string(&path).unwrap();
+        let entry: serde_json::Value = serde_json::from_str(exported.trim()).unwrap();
+        assert_eq!((entry["level"].as_str(), entry["source"].as_str()), (Some("error"), Some("listener")));
+        std::fs::remove_file(path).unwrap();
+    }
+}
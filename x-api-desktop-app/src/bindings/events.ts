@@ -0,0 +1,62 @@
+// This file is generated from src-tauri/src/events.rs. Do not edit by hand.
+// Regenerate with `UPDATE_BINDINGS=1 cargo test bindings` in src-tauri.
+
+export type ProbeResult = { at: number, valid: boolean, latency_ms: number, problems: Array<string>, };
+
+export type NgrokTunnelInfo = { tunnel_id: string, is_active: boolean, url: string | null, reconnects: number, uptime_secs: number, last_probe: ProbeResult | null, deliveries: number, duplicates: number, };
+
+export type TunnelMessage = { tunnel_id: string, message: string, };
+
+export type TunnelUrl = { tunnel_id: string, url: string, };
+
+export type TunnelUrlChange = { tunnel_id: string, previous_url: string, url: string, };
+
+export type ActivityUser = { id: string, screen_name: string | null, };
+
+export type ActivityEventSummary = { event_type: string, id: string | null, action: string | null, actor: ActivityUser | null, target: ActivityUser | null, text: string | null, };
+
+export type ActivitySummary = { for_user_id: string | null, user_has_blocked: boolean | null, event_type: string | null, events: Array<ActivityEventSummary>, unknown_keys: Array<string>, errors: Array<string>, };
+
+export type ForwardResult = { url: string, status: number | null, latency_ms: number, headers: Array<[string, string]>, body: string | null, error: string | null, };
+
+export type WebhookRequestInfo = { tunnel_id: string, endpoint_id: string | null, method: string, uri: string, headers: { [key in string]?: string }, body: string, signature_valid: boolean, activity: ActivitySummary | null, forward: ForwardResult | null, replay_job_id: string | null, fingerprint: string | null, duplicate: boolean, };
+
+export type ReplayOutcome = { event_id: number, result: ForwardResult, };
+
+export type GeneratorProgress = { sent: number, total: number, result: ForwardResult, };
+
+export type GeneratorReport = { sent: number, succeeded: number, failed: number, average_latency_ms: number, stopped: boolean, };
+
+export type SetupStep = "start_tunnel" | "register_webhook" | "validate_crc" | "subscribe" | "unsubscribe" | "delete_webhook" | "stop_tunnel";
+
+export type StepStatus = "started" | "done" | "failed" | "rolled_back" | "skipped";
+
+export type SetupProgress = { tunnel_id: string, step: SetupStep, status: StepStatus, message: string | null, };
+
+export type UploadStage = "init" | "append" | "finalize" | "processing" | "done";
+
+export type MediaUploadProgress = { file_path: string, stage: UploadStage, media_id: string | null, segment_index: number | null, bytes_sent: number, total_bytes: number, processing_percent: number | null, };
+
+export const APP_EVENTS = {
+    ngrokProgress: "ngrok://progress",
+    ngrokUrl: "ngrok://url-obtained",
+    ngrokError: "ngrok://error",
+    ngrokUrlChanged: "ngrok://url-changed",
+    ngrokWebhook: "ngrok://webhook-received",
+    mediaUploadProgress: "media://upload-progress",
+    webhookReplayProgress: "webhook://replay-progress",
+    webhookGeneratorProgress: "webhook://generator-progress",
+    webhookSetupProgress: "webhook://setup-progress",
+} as const;
+
+export interface AppEventPayloads {
+    "ngrok://progress": TunnelMessage;
+    "ngrok://url-obtained": TunnelUrl;
+    "ngrok://error": TunnelMessage;
+    "ngrok://url-changed": TunnelUrlChange;
+    "ngrok://webhook-received": WebhookRequestInfo;
+    "media://upload-progress": MediaUploadProgress;
+    "webhook://replay-progress": ReplayOutcome;
+    "webhook://generator-progress": GeneratorProgress;
+    "webhook://setup-progress": SetupProgress;
+}
@@ -20,20 +20,21 @@ interface CodeSnippetDisplayProps {
   dtabs?: DtabPair[];
   enableTracing?: boolean;
   tfeEnvironment: string;
+  baseUrl?: string; // Selected environment profile's base URL
 }
 
 // --- Try casting the component type to 'any' as a workaround ---
 const Highlighter: any = SyntaxHighlighter; 
 
-const CodeSnippetDisplay: React.FC<CodeSnippetDisplayProps> = ({ endpoint, pathParams, queryParams, bodyParams, expansions, bearerToken, dtabs, enableTracing, tfeEnvironment }) => {
+const CodeSnippetDisplay: React.FC<CodeSnippetDisplayProps> = ({ endpoint, pathParams, queryParams, bodyParams, expansions, bearerToken, dtabs, enableTracing, tfeEnvironment, baseUrl }) => {
   const [selectedLanguage, setSelectedLanguage] = useState<Language>('curl');
 
   // Generate code snippets only when needed
   const codeSnippets = useMemo(() => ({
-    curl: generateCurlCommand(endpoint, pathParams, queryParams, bodyParams, expansions, bearerToken, dtabs, enableTracing, tfeEnvironment),
-    python: generatePythonRequestsCode(endpoint, pathParams, queryParams, bodyParams, expansions, bearerToken, dtabs, enableTracing, tfeEnvironment),
-    javascript: generateJavascriptFetchCode(endpoint, pathParams, queryParams, bodyParams, expansions, bearerToken, dtabs, enableTracing, tfeEnvironment),
-  }), [endpoint, pathParams, queryParams, bodyParams, expansions, bearerToken, dtabs, enableTracing, tfeEnvironment]);
+    curl: generateCurlCommand(endpoint, pathParams, queryParams, bodyParams, expansions, bearerToken, dtabs, enableTracing, tfeEnvironment, baseUrl),
+    python: generatePythonRequestsCode(endpoint, pathParams, queryParams, bodyParams, expansions, bearerToken, dtabs, enableTracing, tfeEnvironment, baseUrl),
+    javascript: generateJavascriptFetchCode(endpoint, pathParams, queryParams, bodyParams, expansions, bearerToken, dtabs, enableTracing, tfeEnvironment, baseUrl),
+  }), [endpoint, pathParams, queryParams, bodyParams, expansions, bearerToken, dtabs, enableTracing, tfeEnvironment, baseUrl]);
 
   const handleCopy = () => {
     const codeToCopy = codeSnippets[selectedLanguage];
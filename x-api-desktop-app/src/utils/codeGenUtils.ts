@@ -1,13 +1,15 @@
 import { Endpoint, DtabPair } from "../types/index";
 
-const BASE_URL = "https://api.twitter.com";
+// Used when no environment profile base URL is given
+const DEFAULT_BASE_URL = "https://api.twitter.com";
 
 // --- Helper to build URL --- 
 function buildUrl(
     endpoint: Endpoint,
     pathParams: Record<string, string>,
     queryParams: Record<string, string>,
-    expansions?: string
+    expansions?: string,
+    baseUrl: string = DEFAULT_BASE_URL
 ): string {
     let path = endpoint.path;
     if (endpoint.pathParams) {
@@ -31,7 +33,7 @@ function buildUrl(
         .map(([key, value]) => `${encodeURIComponent(key)}=${encodeURIComponent(value)}`)
         .join("&");
 
-    return `${BASE_URL}${path}${queryString ? `?${queryString}` : ""}`;
+    return `${baseUrl.replace(/\/+$/, "")}${path}${queryString ? `?${queryString}` : ""}`;
 }
 
 // --- cURL Generator --- 
@@ -44,11 +46,12 @@ export function generateCurlCommand(
   bearerToken?: string | null,
   dtabs?: DtabPair[] | undefined,
   enableTracing?: boolean,
-  tfeEnvironment?: string
+  tfeEnvironment?: string,
+  baseUrl?: string
 ): string {
   if (!endpoint) return "";
 
-  const fullUrl = buildUrl(endpoint, pathParams, queryParams, expansions);
+  const fullUrl = buildUrl(endpoint, pathParams, queryParams, expansions, baseUrl);
   let curlCommand = `curl "${fullUrl}"`;
   
   if (endpoint.method !== 'GET') {
@@ -102,11 +105,12 @@ export function generatePythonRequestsCode(
     bearerToken?: string | null,
     dtabs?: DtabPair[] | undefined,
     enableTracing?: boolean,
-    tfeEnvironment?: string
+    tfeEnvironment?: string,
+    baseUrl?: string
 ): string {
     if (!endpoint) return "";
     
-    const baseUrl = buildUrl(endpoint, pathParams, {}, undefined);
+    const requestUrl = buildUrl(endpoint, pathParams, {}, undefined, baseUrl);
 
     const allQueryParams = { ...queryParams };
     if (expansions) {
@@ -161,7 +165,7 @@ export function generatePythonRequestsCode(
 `import requests
 import json # Import json for payload dump
 
-url = "${baseUrl}"
+url = "${requestUrl}"
 ${paramsDictString}${headersDictString}${bodyJsonString}
 response = requests.request(
     "${endpoint.method}", 
@@ -184,11 +188,12 @@ export function generateJavascriptFetchCode(
     bearerToken?: string | null,
     dtabs?: DtabPair[] | undefined,
     enableTracing?: boolean,
-    tfeEnvironment?: string
+    tfeEnvironment?: string,
+    baseUrl?: string
 ): string {
     if (!endpoint) return "";
 
-    const fullUrl = buildUrl(endpoint, pathParams, queryParams, expansions);
+    const fullUrl = buildUrl(endpoint, pathParams, queryParams, expansions, baseUrl);
     
     // Use provided token or placeholder
     const token = bearerToken || 'YOUR_BEARER_TOKEN';
@@ -16,18 +16,34 @@ import CodeSnippetDisplay from '../components/CodeSnippetDisplay';
 const Highlighter: any = SyntaxHighlighter;
 
 // Define structure for successful backend response
+// Connection details reported by the backend (timing and wire view)
+interface BackendWireInfo {
+  elapsed_ms: number;
+  remote_addr: string | null; // Address actually connected to
+  resolve_overrides: { host: string; port: number; address: string }[]; // Resolve entries that applied
+}
+
 interface BackendApiResponse {
   status: number;
   body: string; // Keep body as raw string
   headers: Record<string, string>;
+  wire?: BackendWireInfo;
 }
 
+// How the backend encodes the request body
+type BackendBodyMode =
+    | { type: 'json' }
+    | { type: 'form' } // body is a flat object, sent form-urlencoded and included in the OAuth1 signature
+    | { type: 'raw'; contentType: string } // body is a string sent verbatim
+    | { type: 'file'; path: string; contentType?: string }; // body read from a local file
+
 // Extend the backend request args structure expectation (frontend side)
 interface BackendRequestArgs {
     method: string;
     url: string;
     headers: Record<string, string>;
     body?: any;
+    bodyMode?: BackendBodyMode; // Defaults to json on the backend
     authType: 'bearer' | 'oauth1a' | 'oauth2'; // Add auth type
     bearerToken?: string; // Optional bearer token
     oauth1Keys?: { // Optional OAuth1 keys
@@ -36,6 +52,7 @@ interface BackendRequestArgs {
         accessToken: string;
         accessSecret: string;
     };
+    profileId?: string; // Backend environment profile (base URL, injected headers, client settings)
     // Add oauth2Keys later if needed
 }
 
@@ -68,7 +85,7 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
   const [selectedExpansions, setSelectedExpansions] = useState<string>('');
   const [isLoading, setIsLoading] = useState<boolean>(false);
   const [apiResponse, setApiResponse] = useState<BackendApiResponse | null>(null);
-  const [apiErrorDetails, setApiErrorDetails] = useState<{ status: number, message: string, body?: any, headers?: Record<string, string> } | null>(null);
+  const [apiErrorDetails, setApiErrorDetails] = useState<{ status: number, message: string, body?: any, headers?: Record<string, string>, wire?: BackendWireInfo } | null>(null);
   const [dtabs, setDtabs] = useState<DtabPair[]>([{ id: Date.now(), from: '', to: '' }]);
   const [enableTracing, setEnableTracing] = useState<boolean>(false);
   const [tfeEnvironment, setTfeEnvironment] = useState<string>('prod');
@@ -77,6 +94,14 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
   const [savedDtabSets, setSavedDtabSets] = useState<Record<string, DtabPair[]>>({});
   const [dtabSetName, setDtabSetName] = useState<string>('');
   const [selectedDtabSet, setSelectedDtabSet] = useState<string>('');
+  const [environmentProfiles, setEnvironmentProfiles] = useState<{ id: string, name: string, baseUrl: string }[]>([]);
+
+  // Load environment profiles from the backend on mount
+  useEffect(() => {
+    invoke<{ id: string, name: string, baseUrl: string }[]>('list_environment_profiles')
+      .then(setEnvironmentProfiles)
+      .catch(error => console.error("Failed to load environment profiles:", error));
+  }, []);
 
   // Load saved sets from localStorage on mount
   useEffect(() => {
@@ -325,10 +350,7 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
   const buildUrl = useCallback(() => {
     if (!endpointDetails) return '';
     
-    // --- Define the Base URL --- 
-    // TODO: Make this configurable or derive from project/app settings if needed
-    const baseUrl = "https://api.twitter.com"; 
-    
+    // The base URL comes from the backend environment profile, so only the path is built here
     let path = endpointDetails.path;
     console.log("buildUrl - Raw path:", path); // Log raw path
     console.log("buildUrl - currentPathParams:", currentPathParams); // Log expected params
@@ -342,8 +364,8 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
       path = path.replace(placeholder, value); 
     }
     
-    // Construct the full URL
-    let fullUrl = baseUrl + path;
+    // Construct the URL relative to the profile's base URL
+    let fullUrl = path;
 
     // --- Append Query Parameters (if any) --- 
     const queryParamsToInclude: Record<string, string> = {};
@@ -377,6 +399,26 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
     
   }, [endpointDetails, pathParamValues, queryParamValues, selectedExpansions, currentPathParams, currentQueryParams]); // Added dependencies
 
+  // Helper to render timing, connected address and effective resolve overrides
+  const renderWireInfo = (wire?: BackendWireInfo) => {
+    if (!wire) return null;
+    return (
+      <details className="response-headers-details">
+        <summary>Timing &amp; Connection ({wire.elapsed_ms} ms)</summary>
+        <p><strong>Connected to:</strong> {wire.remote_addr ?? 'n/a'}</p>
+        {wire.resolve_overrides.length > 0 ? (
+          <ul>
+            {wire.resolve_overrides.map(o => (
+              <li key={`${o.host}:${o.port}`}>{o.host}:{o.port} &rarr; {o.address}</li>
+            ))}
+          </ul>
+        ) : (
+          <p>No resolve overrides applied.</p>
+        )}
+      </details>
+    );
+  };
+
   // Helper function to try pretty-printing JSON
   const formatResponseBody = (rawBody: string): string => {
     try {
@@ -419,10 +461,7 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
     if (enableTracing) {
         headers['X-B3-Flags'] = '1';
     }
-    if (tfeEnvironment === 'staging1' || tfeEnvironment === 'staging2') {
-        headers['X-TFE-Experiment-environment'] = tfeEnvironment;
-        headers['X-Decider-Overrides'] = `tfe_route:des_apiservice_${tfeEnvironment}=on`;
-    }
+    // TFE staging headers are injected by the backend environment profile
     
     // --- Body Preparation (ONLY for methods that have a body) --- 
     if (['POST', 'PUT', 'PATCH'].includes(endpointDetails.method)) {
@@ -485,7 +524,8 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
         body: requestBody, // Pass null if no body was constructed
         authType: authDetails.authType, 
         bearerToken: authDetails.bearerToken, 
-        oauth1Keys: authDetails.oauth1Keys   
+        oauth1Keys: authDetails.oauth1Keys,
+        profileId: tfeEnvironment
     };
 
     console.log("--- Sending API Request Args to Backend ---");
@@ -505,7 +545,8 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
                 status: result.status,
                 message: `Request failed with status ${result.status}`,
                 body: formattedBody, // Use formatted body
-                headers: result.headers
+                headers: result.headers,
+                wire: result.wire
              });
              setApiResponse(null);
         } else {
@@ -519,12 +560,14 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
         let errorMessage: string = "An unknown error occurred.";
         let errorBody: string | null = null;
         let errorHeaders: Record<string, string> | undefined = undefined;
+        let errorWire: BackendWireInfo | undefined = undefined;
 
         // Check if the caught error is an object with expected fields from ApiError
         if (typeof error === 'object' && error !== null) {
             errorStatus = typeof error.status === 'number' ? error.status : 0;
             errorMessage = typeof error.message === 'string' ? error.message : errorMessage;
             errorHeaders = typeof error.headers === 'object' && error.headers !== null ? error.headers : undefined;
+            errorWire = typeof error.wire === 'object' && error.wire !== null ? error.wire : undefined;
             
             // Format the body if it exists and is a string
             if (typeof error.body === 'string') {
@@ -550,7 +593,8 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
             status: errorStatus,
             message: errorMessage,
             body: errorBody,
-            headers: errorHeaders
+            headers: errorHeaders,
+            wire: errorWire
         });
         setApiResponse(null);
     } finally {
@@ -732,9 +776,17 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
                         onChange={(e) => setTfeEnvironment(e.target.value)}
                         className="tfe-env-select"
                       >
-                        <option value="prod">prod</option>
-                        <option value="staging1">staging1</option>
-                        <option value="staging2">staging2</option>
+                        {environmentProfiles.length > 0 ? (
+                          environmentProfiles.map(profile => (
+                            <option key={profile.id} value={profile.id}>{profile.name}</option>
+                          ))
+                        ) : (
+                          <>
+                            <option value="prod">prod</option>
+                            <option value="staging1">staging1</option>
+                            <option value="staging2">staging2</option>
+                          </>
+                        )}
                       </select>
                     </div>
 
@@ -848,6 +900,7 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
                           </Highlighter>
                         </details>
                       )}
+                      {renderWireInfo(apiErrorDetails.wire)}
                     </div>
                     {/* Error Body */} 
                     {apiErrorDetails.body && (
@@ -890,6 +943,7 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
                               </Highlighter>
                          </details>
                        )}
+                       {renderWireInfo(apiResponse.wire)}
                      </div>
  
                      {/* Wrap main body highlighter for styling */} 
@@ -918,6 +972,7 @@ const GenericApiView: React.FC<GenericApiViewProps> = ({
                       dtabs={dtabs.filter(d => d.from.trim() && d.to.trim())} // Pass only active dtabs
                       enableTracing={enableTracing}
                       tfeEnvironment={tfeEnvironment}
+                      baseUrl={environmentProfiles.find(p => p.id === tfeEnvironment)?.baseUrl}
                   />
               </div> 
             </div>
@@ -3,21 +3,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { ApiViewProps, Endpoint, User, Project } from '../types/index'; 
 import GenericApiView from './GenericApiView';
-
-// Type for the Rust state structure
-interface NgrokTunnelInfo {
-    is_active: boolean;
-    url: string | null;
-    console_log: string[];
-}
-
-// Interface for received webhook data
-interface WebhookRequestInfo {
-    method: string;
-    uri: string;
-    headers: Record<string, string>;
-    body: string; // Base64 encoded body
-}
+import { APP_EVENTS, ActivitySummary, ActivityUser, AppEventPayloads, ForwardResult, GeneratorReport, ReplayOutcome, NgrokTunnelInfo, SetupProgress, WebhookRequestInfo } from '../bindings/events';
 
 // Props for the Webhooks view
 interface WebhooksViewProps extends Omit<ApiViewProps, 'setActiveAppId'> { 
@@ -29,6 +15,178 @@ interface WebhooksViewProps extends Omit<ApiViewProps, 'setActiveAppId'> {
     setActiveAppId: (id: number | null) => void; 
 }
 
+// Stored webhook event as returned by list_webhook_events
+interface StoredWebhookEvent extends WebhookRequestInfo {
+    id: number;
+    received_at: number;
+    event_type: string | null;
+    for_user_id: string | null;
+}
+
+interface WebhookEventFilter {
+    eventType?: string;
+    forUserId?: string;
+    replayJobId?: string;
+    duplicate?: boolean;
+    search?: string;
+    offset?: number;
+    limit?: number;
+}
+
+const STORED_EVENTS_PAGE_SIZE = 25;
+
+// How a running endpoint answers, for simulating failures (set_webhook_response_policy)
+interface ResponsePolicy {
+    status: number | null;
+    delayMs: number;
+    dropConnection: boolean;
+    wrongCrc: boolean;
+    failEvery: number | null;
+}
+
+const DEFAULT_RESPONSE_POLICY: ResponsePolicy = { status: null, delayMs: 0, dropConnection: false, wrongCrc: false, failEvery: null };
+
+// ngrok endpoint options, saved per app (get_ngrok_options / start_webhook_listener)
+interface NgrokOptions {
+    domain: string | null;
+    region: string | null;
+    basicAuth: { username: string; password?: string } | null; // The password isn't saved; blank reuses this session's
+    oauth: { provider: string; allowEmails: string[]; allowDomains: string[] } | null;
+    allowCidrs: string[];
+    denyCidrs: string[];
+    requestHeaders: Record<string, string>;
+    metadata: string | null;
+}
+
+const EMPTY_NGROK_OPTIONS: NgrokOptions = {
+    domain: null, region: null, basicAuth: null, oauth: null, allowCidrs: [], denyCidrs: [], requestHeaders: {}, metadata: null,
+};
+
+const splitList = (value: string): string[] => value.split(',').map(item => item.trim()).filter(Boolean);
+
+// A tunnel's console log (get_tunnel_log / export_tunnel_log)
+type LogLevel = 'debug' | 'info' | 'warn' | 'error';
+
+interface LogEntry {
+    seq: number;
+    at: number;
+    level: LogLevel;
+    source: 'session' | 'listener' | 'crc' | 'delivery';
+    message: string;
+}
+
+interface LogPage {
+    entries: LogEntry[];
+    evicted: number;
+    next_seq: number;
+}
+
+const formatLogEntry = (entry: LogEntry): string =>
+    `${new Date(entry.at).toLocaleTimeString()} ${entry.level.toUpperCase()} [${entry.source}] ${entry.message}`;
+
+// What setup_account_activity registered, passed back to teardown_account_activity
+interface WebhookSetup {
+    tunnelId: string;
+    webhookId: string;
+    webhookUrl: string;
+    userId: string | null;
+}
+
+// An Account Activity Replay job (request_activity_replay, list_activity_replays)
+interface ReplayJob {
+    jobId: string;
+    tunnelId: string;
+    webhookId: string;
+    from: number;
+    to: number;
+    state: 'running' | 'complete' | 'failed';
+    description: string | null;
+    requestedAt: number;
+    finishedAt: number | null;
+    replayed: number;
+}
+
+// Local HTTPS certificate (get_local_certificate, generate_local_certificate)
+interface CertificateInfo {
+    hosts: string[];
+    caFingerprint: string;
+    createdAt: number;
+    expiresAt: number;
+    caPath: string;
+}
+
+// Another app served by the same tunnel at /webhooks/{id}
+interface ExtraEndpoint {
+    id: string;
+    consumerSecret: string;
+}
+
+// Result of check_webhook_crc
+interface CrcCheckReport {
+    url: string;
+    crc_token: string;
+    expected_token: string;
+    response_token: string | null;
+    status: number | null;
+    content_type: string | null;
+    latency_ms: number;
+    valid: boolean;
+    problems: string[];
+}
+
+const formatUptime = (secs: number): string => {
+    const hours = Math.floor(secs / 3600);
+    const minutes = Math.floor((secs % 3600) / 60);
+    return hours > 0 ? `${hours}h ${minutes}m` : minutes > 0 ? `${minutes}m ${secs % 60}s` : `${secs}s`;
+};
+
+const formatActivityUser = (user: ActivityUser | null): string =>
+    user ? (user.screen_name ? `@${user.screen_name}` : user.id) : '';
+
+// What the local development server answered for a forwarded delivery
+const renderForward = (forward: ForwardResult | null) => {
+    if (!forward) return null;
+    return (
+        <div style={{ marginBottom: '0.8em' }}>
+            <h5>Sent to {forward.url} ({forward.latency_ms} ms):</h5>
+            {forward.error ? (
+                <div style={{ color: 'var(--error-color, red)' }}>{forward.error}</div>
+            ) : (
+                <pre style={{ whiteSpace: 'pre-wrap', wordBreak: 'break-all', background: 'var(--code-background)', padding: '0.5em', borderRadius: '3px' }}>
+                    {`HTTP ${forward.status}\n${forward.headers.map(([name, value]) => `${name}: ${value}`).join('\n')}\n\n${forward.body ?? ''}`}
+                </pre>
+            )}
+        </div>
+    );
+};
+
+// Decoded Account Activity events, one line each
+const renderActivity = (activity: ActivitySummary | null) => {
+    if (!activity) return null;
+    return (
+        <div style={{ marginBottom: '0.8em' }}>
+            <h5>Activity{activity.for_user_id && ` for user ${activity.for_user_id}`}:</h5>
+            <ul style={{ margin: 0, paddingLeft: '1.2em' }}>
+                {activity.events.map((event, index) => (
+                    <li key={index}>
+                        <code>{event.event_type}</code>
+                        {event.action && ` ${event.action}`}
+                        {event.actor && ` by ${formatActivityUser(event.actor)}`}
+                        {event.target && ` → ${formatActivityUser(event.target)}`}
+                        {event.text && `: ${event.text}`}
+                    </li>
+                ))}
+            </ul>
+            {activity.unknown_keys.length > 0 && (
+                <small style={{ color: 'var(--text-color-secondary)' }}>Unrecognized keys: {activity.unknown_keys.join(', ')}</small>
+            )}
+            {activity.errors.map((error, index) => (
+                <small key={index} style={{ display: 'block', color: 'var(--error-color, red)' }}>Could not decode {error}</small>
+            ))}
+        </div>
+    );
+};
+
 // Define endpoint data specific to Webhooks API with categories
 const webhooksEndpoints: Endpoint[] = [
     // Webhook Management Category
@@ -94,24 +252,120 @@ const WebhooksView: React.FC<WebhooksViewProps> = (props) => {
     // State for Webhook Testing
     const [isWebhookSetupActive, setIsWebhookSetupActive] = useState<boolean>(false);
     const [ngrokToken, setNgrokToken] = useState<string>('');
+    const [useLocalHttps, setUseLocalHttps] = useState<boolean>(false);
+    const [localHttpsPort, setLocalHttpsPort] = useState<string>('8443');
+    const [localCertificate, setLocalCertificate] = useState<CertificateInfo | null>(null);
+    const [certificateHosts, setCertificateHosts] = useState<string>('');
+    const [caExportPath, setCaExportPath] = useState<string>('');
+    const [certificateStatus, setCertificateStatus] = useState<string | null>(null);
     const [consumerSecret, setConsumerSecret] = useState<string>('');
+    const [rejectInvalidSignatures, setRejectInvalidSignatures] = useState<boolean>(false);
+    const [dedupWindowSecs, setDedupWindowSecs] = useState<string>('600');
+    const [forwardUrl, setForwardUrl] = useState<string>('');
+    const [returnDownstream, setReturnDownstream] = useState<boolean>(false);
+    const [extraEndpoints, setExtraEndpoints] = useState<ExtraEndpoint[]>([]);
+    const [ngrokOptions, setNgrokOptions] = useState<NgrokOptions>(EMPTY_NGROK_OPTIONS);
+    const [policyEndpointId, setPolicyEndpointId] = useState<string>('');
+    const [responsePolicy, setResponsePolicy] = useState<ResponsePolicy>(DEFAULT_RESPONSE_POLICY);
+    const [policyStatus, setPolicyStatus] = useState<string | null>(null);
     const [consoleOutput, setConsoleOutput] = useState<string[]>([]);
+    const [consoleLevel, setConsoleLevel] = useState<LogLevel>('info');
+    const [logExportPath, setLogExportPath] = useState<string>('');
+    const [logExportStatus, setLogExportStatus] = useState<string | null>(null);
     const [webhookUrl, setWebhookUrl] = useState<string | null>(null);
     const [setupError, setSetupError] = useState<string | null>(null);
     const [receivedWebhooks, setReceivedWebhooks] = useState<WebhookRequestInfo[]>([]);
+    const [storedEvents, setStoredEvents] = useState<StoredWebhookEvent[]>([]);
+    const [storedTotal, setStoredTotal] = useState<number>(0);
+    const [storedOffset, setStoredOffset] = useState<number>(0);
+    const [storedSearch, setStoredSearch] = useState<string>('');
+    const [storedEventType, setStoredEventType] = useState<string>('');
+    const [storedReplayJobId, setStoredReplayJobId] = useState<string>('');
+    const [storedUserId, setStoredUserId] = useState<string>('');
+    const [storedDuplicatesOnly, setStoredDuplicatesOnly] = useState<boolean>(false);
+    const [storedError, setStoredError] = useState<string | null>(null);
+    const [replayUrl, setReplayUrl] = useState<string>('');
+    const [replaySecret, setReplaySecret] = useState<string>('');
+    const [replayIntervalMs, setReplayIntervalMs] = useState<string>('');
+    const [replayOutcomes, setReplayOutcomes] = useState<Record<number, ReplayOutcome>>({});
+    const [generatorKind, setGeneratorKind] = useState<string>('tweet_create');
+    const [generatorUserId, setGeneratorUserId] = useState<string>('');
+    const [generatorTarget, setGeneratorTarget] = useState<string>('');
+    const [generatorLoad, setGeneratorLoad] = useState<string>('once');
+    const [generatorRate, setGeneratorRate] = useState<string>('1');
+    const [generatorCount, setGeneratorCount] = useState<string>('10');
+    const [generatorRunning, setGeneratorRunning] = useState<boolean>(false);
+    const [generatorStatus, setGeneratorStatus] = useState<string | null>(null);
+    const [crcCheckUrl, setCrcCheckUrl] = useState<string>('');
+    const [crcCheckReport, setCrcCheckReport] = useState<CrcCheckReport | null>(null);
+    const [crcCheckError, setCrcCheckError] = useState<string | null>(null);
+    const [setupSteps, setSetupSteps] = useState<SetupProgress[]>([]);
+    const [accountActivitySetup, setAccountActivitySetup] = useState<WebhookSetup | null>(null);
+    const [setupRunning, setSetupRunning] = useState<boolean>(false);
+    const [accountActivityError, setAccountActivityError] = useState<string | null>(null);
+    const [replayFrom, setReplayFrom] = useState<string>('');
+    const [replayTo, setReplayTo] = useState<string>('');
+    const [replayJobs, setReplayJobs] = useState<ReplayJob[]>([]);
+    const [replayJobError, setReplayJobError] = useState<string | null>(null);
+    const [replayDeliveries, setReplayDeliveries] = useState<number>(0);
+    const [runningTunnels, setRunningTunnels] = useState<NgrokTunnelInfo[]>([]);
+    const [urlChangeNotice, setUrlChangeNotice] = useState<string | null>(null);
     const consoleEndRef = useRef<HTMLDivElement>(null);
     const consoleContainerRef = useRef<HTMLDivElement>(null); 
 
+    // Each app gets its own tunnel, so prod and staging can listen at the same time
+    const tunnelId = props.activeAppId !== null ? String(props.activeAppId) : 'default';
+    const activeApp = props.projects.flatMap(project => project.apps).find(app => app.id === props.activeAppId) ?? null;
+
+    // Replaces the console with the tunnel's stored log at `level` and above
+    const loadConsoleLog = async (level: LogLevel) => {
+        try {
+            const page = await invoke<LogPage | null>('get_tunnel_log', { tunnelId, query: { minLevel: level } });
+            const lines = (page?.entries ?? []).map(formatLogEntry);
+            if (page && page.evicted > 0) {
+                lines.unshift(`(${page.evicted} older entries were dropped)`);
+            }
+            setConsoleOutput(lines);
+        } catch (err) {
+            console.error("Failed to load tunnel log:", err);
+        }
+    };
+
+    const handleExportLog = async () => {
+        setLogExportStatus(null);
+        try {
+            const count = await invoke<number>('export_tunnel_log', { tunnelId, path: logExportPath, query: { minLevel: consoleLevel } });
+            setLogExportStatus(`Exported ${count} entries.`);
+        } catch (err: any) {
+            setLogExportStatus(`Export failed: ${err.toString()}`);
+        }
+    };
+
+    const refreshRunningTunnels = async () => {
+        try {
+            setRunningTunnels(await invoke<NgrokTunnelInfo[]>('list_webhook_tunnels'));
+        } catch (err) {
+            console.error("Failed to list tunnels:", err);
+        }
+    };
+
+    // Switching apps shows the other tunnel's state, not this one's
+    useEffect(() => {
+        setConsoleOutput([]);
+        setWebhookUrl(null);
+        setIsWebhookSetupActive(false);
+    }, [tunnelId]);
+
     // Effect to listen for Tauri events AND fetch initial state for Ngrok
     useEffect(() => {
         let isMounted = true; 
 
         const fetchInitialState = async () => {
             try {
-                const initialState = await invoke<NgrokTunnelInfo | null>('get_ngrok_status');
+                const initialState = await invoke<NgrokTunnelInfo | null>('get_ngrok_status', { tunnelId });
                 if (isMounted && initialState) {
                     console.log("Fetched initial ngrok state:", initialState);
-                    setConsoleOutput(initialState.console_log || []); 
+                    loadConsoleLog(consoleLevel);
                     setWebhookUrl(initialState.url);
                     setIsWebhookSetupActive(initialState.is_active);
                     if (initialState.is_active) {
@@ -125,33 +379,63 @@ const WebhooksView: React.FC<WebhooksViewProps> = (props) => {
         };
 
         fetchInitialState();
+        refreshRunningTunnels();
+        invoke<NgrokOptions | null>('get_ngrok_options', { tunnelId })
+            .then(options => { if (isMounted) setNgrokOptions(options ?? EMPTY_NGROK_OPTIONS); })
+            .catch(err => console.error("Failed to load ngrok options:", err));
 
         console.log("Setting up event listeners for WebhooksView..."); 
         const listeners = Promise.all([
-            listen<string>('ngrok://progress', (event) => {
+            listen<AppEventPayloads[typeof APP_EVENTS.ngrokProgress]>(APP_EVENTS.ngrokProgress, (event) => {
                console.log('Received ngrok://progress event:', event.payload);
-               if (isMounted) {
-                   setConsoleOutput(prev => [...prev, event.payload]);
+               if (isMounted && event.payload.tunnel_id === tunnelId) {
+                   setConsoleOutput(prev => [...prev, event.payload.message]);
                }
             }),
-            listen<string>('ngrok://url-obtained', (event) => {
+            listen<AppEventPayloads[typeof APP_EVENTS.ngrokUrl]>(APP_EVENTS.ngrokUrl, (event) => {
                console.log('Received ngrok://url-obtained event:', event.payload);
                if (isMounted) {
-                   setWebhookUrl(event.payload);
+                   refreshRunningTunnels();
+                   if (event.payload.tunnel_id === tunnelId) setWebhookUrl(event.payload.url);
                }
             }),
-            listen<string>('ngrok://error', (event) => {
+            listen<AppEventPayloads[typeof APP_EVENTS.ngrokUrlChanged]>(APP_EVENTS.ngrokUrlChanged, (event) => {
+               console.log('Received ngrok://url-changed event:', event.payload);
+               if (isMounted && event.payload.tunnel_id === tunnelId) {
+                   setUrlChangeNotice(`The tunnel reconnected at ${event.payload.url} (was ${event.payload.previous_url}). Update the webhook registered with X.`);
+               }
+            }),
+            listen<AppEventPayloads[typeof APP_EVENTS.webhookSetupProgress]>(APP_EVENTS.webhookSetupProgress, (event) => {
+               if (isMounted && event.payload.tunnel_id === tunnelId) {
+                   setSetupSteps(prev => [...prev, event.payload]);
+               }
+            }),
+            listen<AppEventPayloads[typeof APP_EVENTS.ngrokError]>(APP_EVENTS.ngrokError, (event) => {
                 console.log('Received ngrok://error event:', event.payload);
-                if (isMounted) {
-                    setSetupError(event.payload);
-                    setConsoleOutput(prev => [...prev, event.payload]); 
+                if (isMounted && event.payload.tunnel_id === tunnelId) {
+                    setSetupError(event.payload.message);
+                    setConsoleOutput(prev => [...prev, event.payload.message]); 
                     setIsWebhookSetupActive(false); 
                 }
             }),
-            listen<WebhookRequestInfo>('ngrok://webhook-received', (event) => {
+            listen<AppEventPayloads[typeof APP_EVENTS.webhookReplayProgress]>(APP_EVENTS.webhookReplayProgress, (event) => {
+                 if (isMounted) {
+                    setReplayOutcomes(prev => ({ ...prev, [event.payload.event_id]: event.payload }));
+                 }
+            }),
+            listen<AppEventPayloads[typeof APP_EVENTS.webhookGeneratorProgress]>(APP_EVENTS.webhookGeneratorProgress, (event) => {
+                 if (isMounted) {
+                    const { sent, total, result } = event.payload;
+                    setGeneratorStatus(`Sent ${sent} of ${total} (last: ${result.status ?? result.error})`);
+                 }
+            }),
+            listen<AppEventPayloads[typeof APP_EVENTS.ngrokWebhook]>(APP_EVENTS.ngrokWebhook, (event) => {
                  console.log('Received ngrok://webhook-received event:', event.payload);
                  if (isMounted) {
                     setReceivedWebhooks(prev => [event.payload, ...prev]); 
+                    if (event.payload.replay_job_id) {
+                        setReplayDeliveries(count => count + 1);
+                    }
                  }
             })
         ]);
@@ -163,19 +447,33 @@ const WebhooksView: React.FC<WebhooksViewProps> = (props) => {
                 unlisteners.forEach(unlisten => unlisten());
             });
         };
-    }, [consoleOutput]); 
+    }, [tunnelId]); 
 
     const handleWebhookSetupClick = async () => {
         setConsoleOutput([]);
         setWebhookUrl(null);
         setSetupError(null);
-        setReceivedWebhooks([]);
+        setReceivedWebhooks(prev => prev.filter(hook => hook.tunnel_id !== tunnelId));
         setIsWebhookSetupActive(true); 
 
         try {
-            await invoke('start_ngrok_webhook', { 
-                authToken: ngrokToken, 
-                consumerSecret: consumerSecret
+            const endpoints = Object.fromEntries(
+                extraEndpoints
+                    .filter(endpoint => endpoint.id && endpoint.consumerSecret)
+                    .map(endpoint => [endpoint.id, { consumerSecret: endpoint.consumerSecret, rejectInvalidSignatures: rejectInvalidSignatures, forward: null }])
+            );
+            await invoke('start_webhook_listener', { 
+                tunnelId: tunnelId,
+                provider: useLocalHttps
+                    ? { type: 'localTls', port: parseInt(localHttpsPort, 10) || 0 }
+                    : { type: 'ngrok', authToken: ngrokToken, options: ngrokOptions },
+                endpoint: {
+                    consumerSecret: consumerSecret,
+                    rejectInvalidSignatures: rejectInvalidSignatures,
+                    forward: forwardUrl ? { url: forwardUrl, returnDownstream: returnDownstream } : null,
+                },
+                endpoints: endpoints,
+                options: { dedupWindowSecs: dedupWindowSecs === '' ? null : Math.max(0, parseInt(dedupWindowSecs, 10) || 0) },
             });
         } catch (err: any) {
             const errorMsg = `Failed to invoke ngrok setup command: ${err.toString()}`;
@@ -186,6 +484,250 @@ const WebhooksView: React.FC<WebhooksViewProps> = (props) => {
         }
     };
 
+    useEffect(() => {
+        invoke<CertificateInfo | null>('get_local_certificate')
+            .then(setLocalCertificate)
+            .catch((err: any) => setCertificateStatus(err.toString()));
+    }, []);
+
+    const generateLocalCertificate = async () => {
+        const hosts = certificateHosts.split(',').map(host => host.trim()).filter(Boolean);
+        try {
+            setLocalCertificate(await invoke<CertificateInfo>('generate_local_certificate', { hosts: hosts.length ? hosts : null }));
+            setCertificateStatus('Generated a new CA; test clients need to trust it again');
+        } catch (err: any) {
+            setCertificateStatus(`Failed to generate certificate: ${err.toString()}`);
+        }
+    };
+
+    const exportLocalCa = async () => {
+        try {
+            await invoke('export_local_ca', { path: caExportPath });
+            setCertificateStatus(`Exported CA certificate to ${caExportPath}`);
+        } catch (err: any) {
+            setCertificateStatus(`Failed to export CA certificate: ${err.toString()}`);
+        }
+    };
+
+    const handleWebhookStopClick = async (stopTunnelId: string = tunnelId) => {
+        try {
+            await invoke('stop_ngrok_webhook', { tunnelId: stopTunnelId });
+            if (stopTunnelId === tunnelId) {
+                setWebhookUrl(null);
+                setIsWebhookSetupActive(false);
+                setUrlChangeNotice(null);
+            }
+            await refreshRunningTunnels();
+        } catch (err: any) {
+            const errorMsg = `Failed to stop ngrok tunnel: ${err.toString()}`;
+            console.error("Error during invoke:", err);
+            setSetupError(errorMsg);
+            setConsoleOutput(prev => [...prev, errorMsg]);
+        }
+    };
+
+    const loadResponsePolicy = async (endpointId: string) => {
+        setPolicyEndpointId(endpointId);
+        try {
+            setResponsePolicy(await invoke<ResponsePolicy>('get_webhook_response_policy', { tunnelId, endpointId: endpointId || null }));
+            setPolicyStatus(null);
+        } catch (err: any) {
+            setPolicyStatus(`Failed to load policy: ${err.toString()}`);
+        }
+    };
+
+    const applyResponsePolicy = async (policy: ResponsePolicy) => {
+        try {
+            await invoke('set_webhook_response_policy', { tunnelId, endpointId: policyEndpointId || null, policy });
+            setResponsePolicy(policy);
+            setPolicyStatus(policy === DEFAULT_RESPONSE_POLICY ? 'Answering normally' : 'Policy applied');
+        } catch (err: any) {
+            setPolicyStatus(`Failed to apply policy: ${err.toString()}`);
+        }
+    };
+
+    const storedFilter = (): WebhookEventFilter => ({
+        eventType: storedEventType || undefined,
+        forUserId: storedUserId || undefined,
+        duplicate: storedDuplicatesOnly || undefined,
+        replayJobId: storedReplayJobId || undefined,
+        search: storedSearch || undefined,
+    });
+
+    const loadStoredEvents = async (offset: number) => {
+        try {
+            const page = await invoke<{ events: StoredWebhookEvent[]; total: number }>('list_webhook_events', {
+                filter: { ...storedFilter(), offset, limit: STORED_EVENTS_PAGE_SIZE },
+            });
+            setStoredEvents(page.events);
+            setStoredTotal(page.total);
+            setStoredOffset(offset);
+            setStoredError(null);
+        } catch (err: any) {
+            setStoredError(`Failed to load stored events: ${err.toString()}`);
+        }
+    };
+
+    const deleteStoredEvents = async (ids: number[] | null) => {
+        const filter = storedFilter();
+        // With no search fields set, "matching" means every stored event
+        const all = !ids && Object.values(filter).every(value => value === undefined);
+        if (all && !confirm("No filter is set. Delete every stored event?")) {
+            return;
+        }
+        try {
+            await invoke<number>('delete_webhook_events', { ids, filter: ids ? null : filter, all });
+            await loadStoredEvents(ids ? storedOffset : 0);
+        } catch (err: any) {
+            setStoredError(`Failed to delete stored events: ${err.toString()}`);
+        }
+    };
+
+    const replayStoredEvents = async (eventIds: number[]) => {
+        try {
+            const interval = parseInt(replayIntervalMs, 10);
+            await invoke<ReplayOutcome[]>('replay_webhook_events', {
+                args: {
+                    eventIds,
+                    targetUrl: replayUrl,
+                    consumerSecret: replaySecret || null,
+                    pacing: interval > 0 ? { type: 'fixed', intervalMs: interval } : { type: 'none' },
+                },
+            });
+            setStoredError(null);
+        } catch (err: any) {
+            setStoredError(`Failed to replay events: ${err.toString()}`);
+        }
+    };
+
+    const runCrcCheck = async () => {
+        setCrcCheckReport(null);
+        setCrcCheckError(null);
+        try {
+            setCrcCheckReport(await invoke<CrcCheckReport>('check_webhook_crc', { url: crcCheckUrl, consumerSecret: consumerSecret }));
+        } catch (err: any) {
+            setCrcCheckError(err.toString());
+        }
+    };
+
+    // Starts the tunnel, registers it with X and subscribes the app owner in one go
+    const runAccountActivitySetup = async () => {
+        const keys = activeApp?.oauth1Keys;
+        if (!keys?.bearerToken || !keys.apiKey || !keys.apiSecret || !keys.accessToken || !keys.accessSecret) {
+            setAccountActivityError('The active app needs a bearer token and OAuth 1.0a keys.');
+            return;
+        }
+        setSetupSteps([]);
+        setAccountActivityError(null);
+        setSetupRunning(true);
+        try {
+            const setup = await invoke<WebhookSetup>('setup_account_activity', {
+                args: {
+                    tunnelId: tunnelId,
+                    provider: { type: 'ngrok', authToken: ngrokToken, options: ngrokOptions },
+                    endpoint: { consumerSecret: consumerSecret, rejectInvalidSignatures: rejectInvalidSignatures, forward: null },
+                    bearerToken: keys.bearerToken,
+                    oauth1Keys: { apiKey: keys.apiKey, apiSecret: keys.apiSecret, accessToken: keys.accessToken, accessSecret: keys.accessSecret },
+                },
+            });
+            setAccountActivitySetup(setup);
+            setIsWebhookSetupActive(true);
+        } catch (err: any) {
+            setAccountActivityError(err.toString());
+        } finally {
+            setSetupRunning(false);
+            refreshRunningTunnels();
+        }
+    };
+
+    const runAccountActivityTeardown = async () => {
+        setSetupSteps([]);
+        setAccountActivityError(null);
+        setSetupRunning(true);
+        try {
+            await invoke('teardown_account_activity', {
+                args: { tunnelId: tunnelId, bearerToken: activeApp?.oauth1Keys?.bearerToken ?? '', setup: accountActivitySetup },
+            });
+            setAccountActivitySetup(null);
+        } catch (err: any) {
+            setAccountActivityError(err.toString());
+        } finally {
+            setSetupRunning(false);
+            setWebhookUrl(null);
+            setIsWebhookSetupActive(false);
+            refreshRunningTunnels();
+        }
+    };
+
+    const refreshReplayJobs = async () => {
+        try {
+            setReplayJobs(await invoke<ReplayJob[]>('list_activity_replays', { tunnelId: tunnelId }));
+        } catch (err: any) {
+            setReplayJobError(err.toString());
+        }
+    };
+
+    // Job counts and states change as tagged deliveries arrive
+    useEffect(() => {
+        refreshReplayJobs();
+    }, [tunnelId, replayDeliveries]);
+
+    const requestActivityReplay = async () => {
+        const from = new Date(replayFrom).getTime();
+        const to = new Date(replayTo).getTime();
+        if (isNaN(from) || isNaN(to)) {
+            setReplayJobError('Pick both a start and an end time.');
+            return;
+        }
+        setReplayJobError(null);
+        try {
+            await invoke<ReplayJob>('request_activity_replay', {
+                args: {
+                    tunnelId: tunnelId,
+                    webhookId: accountActivitySetup?.webhookId ?? null,
+                    from,
+                    to,
+                    bearerToken: activeApp?.oauth1Keys?.bearerToken ?? '',
+                },
+            });
+        } catch (err: any) {
+            setReplayJobError(err.toString());
+        } finally {
+            refreshReplayJobs();
+        }
+    };
+
+    const runGenerator = async () => {
+        const count = Math.max(1, parseInt(generatorCount, 10) || 1);
+        const rate = Math.max(0.1, parseFloat(generatorRate) || 1);
+        const load = generatorLoad === 'steady'
+            ? { type: 'steady', ratePerSec: rate, durationSecs: Math.ceil(count / rate) }
+            : generatorLoad === 'bursty'
+                ? { type: 'bursty', burstSize: count, bursts: 5, intervalMs: 2000 }
+                : { type: 'once' };
+        setGeneratorRunning(true);
+        setGeneratorStatus('Starting...');
+        try {
+            const report = await invoke<GeneratorReport>('run_webhook_generator', {
+                args: {
+                    targetUrl: generatorTarget || webhookUrl,
+                    consumerSecret: consumerSecret,
+                    events: [{ kind: generatorKind, forUserId: generatorUserId || '1', actor: null, text: null, overrides: null }],
+                    load,
+                },
+            });
+            setGeneratorStatus(`${report.stopped ? 'Stopped' : 'Done'}: ${report.succeeded} succeeded, ${report.failed} failed, avg ${report.average_latency_ms} ms`);
+        } catch (err: any) {
+            setGeneratorStatus(`Generator failed: ${err.toString()}`);
+        } finally {
+            setGeneratorRunning(false);
+        }
+    };
+
+    useEffect(() => {
+        loadStoredEvents(0);
+    }, [receivedWebhooks.length]);
+
     const decodeBody = (base64Body: string): string => {
         try {
             return atob(base64Body);
@@ -200,7 +742,7 @@ const WebhooksView: React.FC<WebhooksViewProps> = (props) => {
         <details className="advanced-details" style={{ marginTop: '20px' }} open={isWebhookSetupActive || !!setupError}>
             <summary className="advanced-summary">Test Webhooks</summary>
             <div className="advanced-section-content">
-                <p>Use this section to stand up a temporary webhook endpoint using ngrok to test receiving events and handling CRC checks.</p>
+                <p>Use this section to stand up a temporary webhook endpoint using ngrok to test receiving events and handling CRC checks. Each app gets its own tunnel (this one is <code>{tunnelId}</code>), so several can run at once.</p>
                 
                 <div className="form-group" style={{ marginBottom: '1em' }}> 
                     <label htmlFor="ngrok-token-input" style={{ display: 'block', marginBottom: '0.4em', fontWeight: '500' }}>
@@ -240,16 +782,192 @@ const WebhooksView: React.FC<WebhooksViewProps> = (props) => {
                     </small>
                 </div>
 
+                <div className="form-group" style={{ marginBottom: '1em' }}> 
+                    <label style={{ display: 'flex', alignItems: 'center', gap: '0.5em' }}>
+                        <input
+                            type="checkbox"
+                            checked={rejectInvalidSignatures}
+                            onChange={(e) => setRejectInvalidSignatures(e.target.checked)}
+                            disabled={isWebhookSetupActive}
+                        />
+                        Reject deliveries with a missing or invalid signature (401)
+                    </label>
+                    <small style={{ display: 'block', marginTop: '0.5em', fontSize: '0.8em', color: 'var(--text-color-secondary)' }}>
+                        Deliveries are checked against the x-twitter-webhooks-signature header either way; rejected ones still show up below.
+                    </small>
+                </div>
+
+                <div className="form-group" style={{ marginBottom: '1em' }}> 
+                    <label style={{ display: 'flex', alignItems: 'center', gap: '0.5em' }}>
+                        Flag repeats of the same event within
+                        <input className="text-input" type="number" min="0" value={dedupWindowSecs} onChange={(e) => setDedupWindowSecs(e.target.value)} disabled={isWebhookSetupActive} style={{ width: '6em' }} />
+                        seconds
+                    </label>
+                    <small style={{ display: 'block', marginTop: '0.5em', fontSize: '0.8em', color: 'var(--text-color-secondary)' }}>
+                        Events are matched by their ids (or the body hash when they have none), for testing that your consumer handles redeliveries idempotently. 0 turns flagging off.
+                    </small>
+                </div>
+
+                <div className="form-group" style={{ marginBottom: '1em' }}> 
+                    <label htmlFor="forward-url-input" style={{ display: 'block', marginBottom: '0.4em', fontWeight: '500' }}>
+                        Forward To (optional):
+                    </label>
+                    <input
+                        id="forward-url-input"
+                        type="text"
+                        className="text-input"
+                        placeholder="http://localhost:3000/webhook"
+                        value={forwardUrl}
+                        onChange={(e) => setForwardUrl(e.target.value)}
+                        disabled={isWebhookSetupActive}
+                        style={{ width: '100%', boxSizing: 'border-box' }}
+                    />
+                    <label style={{ display: 'flex', alignItems: 'center', gap: '0.5em', marginTop: '0.5em' }}>
+                        <input
+                            type="checkbox"
+                            checked={returnDownstream}
+                            onChange={(e) => setReturnDownstream(e.target.checked)}
+                            disabled={isWebhookSetupActive || !forwardUrl}
+                        />
+                        Answer X with the local server's response (including CRC checks)
+                    </label>
+                </div>
+
+                <details className="advanced-details" style={{ marginBottom: '1em' }}>
+                    <summary className="advanced-summary">ngrok Endpoint Options</summary>
+                    <div className="advanced-section-content" style={{ display: 'grid', gridTemplateColumns: 'max-content 1fr', gap: '0.5em 0.8em', alignItems: 'center' }}>
+                        <label>Domain</label>
+                        <input className="text-input" placeholder="my-app.ngrok.app" value={ngrokOptions.domain ?? ''} disabled={isWebhookSetupActive}
+                            onChange={(e) => setNgrokOptions({ ...ngrokOptions, domain: e.target.value.trim() || null })} />
+                        <label>Region</label>
+                        <input className="text-input" placeholder="closest" value={ngrokOptions.region ?? ''} disabled={isWebhookSetupActive}
+                            onChange={(e) => setNgrokOptions({ ...ngrokOptions, region: e.target.value.trim() || null })} />
+                        <label>Basic auth</label>
+                        <div style={{ display: 'flex', gap: '0.5em' }}>
+                            <input className="text-input" placeholder="Username" value={ngrokOptions.basicAuth?.username ?? ''} disabled={isWebhookSetupActive}
+                                onChange={(e) => setNgrokOptions({ ...ngrokOptions, basicAuth: e.target.value || ngrokOptions.basicAuth?.password ? { username: e.target.value, password: ngrokOptions.basicAuth?.password ?? '' } : null })} />
+                            <input className="text-input" type="password" placeholder="Password (8+ characters)" value={ngrokOptions.basicAuth?.password ?? ''} disabled={isWebhookSetupActive}
+                                onChange={(e) => setNgrokOptions({ ...ngrokOptions, basicAuth: e.target.value || ngrokOptions.basicAuth?.username ? { username: ngrokOptions.basicAuth?.username ?? '', password: e.target.value } : null })} />
+                        </div>
+                        <label>OAuth</label>
+                        <div style={{ display: 'flex', gap: '0.5em' }}>
+                            <input className="text-input" placeholder="Provider, e.g. google" value={ngrokOptions.oauth?.provider ?? ''} disabled={isWebhookSetupActive}
+                                onChange={(e) => setNgrokOptions({ ...ngrokOptions, oauth: e.target.value ? { provider: e.target.value.trim(), allowEmails: ngrokOptions.oauth?.allowEmails ?? [], allowDomains: ngrokOptions.oauth?.allowDomains ?? [] } : null })} />
+                            <input className="text-input" placeholder="Allowed emails, comma separated" value={ngrokOptions.oauth?.allowEmails.join(', ') ?? ''} disabled={isWebhookSetupActive || !ngrokOptions.oauth}
+                                onChange={(e) => ngrokOptions.oauth && setNgrokOptions({ ...ngrokOptions, oauth: { ...ngrokOptions.oauth, allowEmails: splitList(e.target.value) } })} />
+                        </div>
+                        <label>Allow CIDRs</label>
+                        <input className="text-input" placeholder="e.g. 199.16.156.0/22, comma separated" value={ngrokOptions.allowCidrs.join(', ')} disabled={isWebhookSetupActive}
+                            onChange={(e) => setNgrokOptions({ ...ngrokOptions, allowCidrs: splitList(e.target.value) })} />
+                        <label>Deny CIDRs</label>
+                        <input className="text-input" placeholder="comma separated" value={ngrokOptions.denyCidrs.join(', ')} disabled={isWebhookSetupActive}
+                            onChange={(e) => setNgrokOptions({ ...ngrokOptions, denyCidrs: splitList(e.target.value) })} />
+                        <label>Request header</label>
+                        <input className="text-input" placeholder="Name: value" value={Object.entries(ngrokOptions.requestHeaders).map(([name, value]) => `${name}: ${value}`).join('; ')} disabled={isWebhookSetupActive}
+                            onChange={(e) => setNgrokOptions({ ...ngrokOptions, requestHeaders: Object.fromEntries(e.target.value.split(';').map(pair => pair.split(':').map(part => part.trim())).filter(([name, value]) => name && value !== undefined).map(([name, ...rest]) => [name, rest.join(':')])) })} />
+                        <label>Metadata</label>
+                        <input className="text-input" placeholder="Shown in the ngrok dashboard" value={ngrokOptions.metadata ?? ''} disabled={isWebhookSetupActive}
+                            onChange={(e) => setNgrokOptions({ ...ngrokOptions, metadata: e.target.value || null })} />
+                    </div>
+                    <small style={{ display: 'block', marginTop: '0.5em', fontSize: '0.8em', color: 'var(--text-color-secondary)' }}>
+                        Saved for this app when the tunnel starts, so a reserved domain keeps the registered webhook URL the same across restarts.
+                    </small>
+                </details>
+
+                <details className="advanced-details" style={{ marginBottom: '1em' }} open={useLocalHttps}>
+                    <summary className="advanced-summary">Local HTTPS (no tunnel)</summary>
+                    <div className="advanced-section-content">
+                        <p>Serves the receiver over HTTPS on this machine with a locally generated certificate. Test clients must trust the exported CA certificate.</p>
+                        <label style={{ display: 'flex', alignItems: 'center', gap: '0.5em', marginBottom: '0.8em' }}>
+                            <input type="checkbox" checked={useLocalHttps} onChange={(e) => setUseLocalHttps(e.target.checked)} disabled={isWebhookSetupActive || !localCertificate} />
+                            Serve on https://127.0.0.1:
+                            <input className="text-input" type="number" min="0" max="65535" value={localHttpsPort} onChange={(e) => setLocalHttpsPort(e.target.value)} disabled={isWebhookSetupActive} style={{ width: '6em' }} />
+                            instead of ngrok
+                        </label>
+                        {localCertificate ? (
+                            <div style={{ marginBottom: '0.8em' }}>
+                                Covers <code>{localCertificate.hosts.join(', ')}</code> until {new Date(localCertificate.expiresAt).toLocaleDateString()}
+                                <br />CA SHA-256 <code style={{ wordBreak: 'break-all' }}>{localCertificate.caFingerprint}</code>
+                                <br />CA file <code>{localCertificate.caPath}</code>
+                            </div>
+                        ) : (
+                            <p style={{ fontStyle: 'italic', color: 'var(--text-color-secondary)' }}>No certificate generated yet.</p>
+                        )}
+                        <div style={{ display: 'flex', gap: '0.5em', marginBottom: '0.8em' }}>
+                            <input className="text-input" placeholder="Hosts (default: localhost, 127.0.0.1, ::1)" value={certificateHosts} onChange={(e) => setCertificateHosts(e.target.value)} style={{ flex: 1 }} />
+                            <button className="run-button" onClick={generateLocalCertificate}>{localCertificate ? 'Regenerate' : 'Generate'}</button>
+                        </div>
+                        <div style={{ display: 'flex', gap: '0.5em', marginBottom: '0.8em' }}>
+                            <input className="text-input" placeholder="Export CA to (e.g. /tmp/x-api-local-ca.pem)" value={caExportPath} onChange={(e) => setCaExportPath(e.target.value)} style={{ flex: 1 }} />
+                            <button className="run-button" onClick={exportLocalCa} disabled={!caExportPath || !localCertificate}>Export CA</button>
+                        </div>
+                        {certificateStatus && <div>{certificateStatus}</div>}
+                    </div>
+                </details>
+
+                <div className="form-group" style={{ marginBottom: '1em' }}> 
+                    <label style={{ display: 'block', marginBottom: '0.4em', fontWeight: '500' }}>
+                        More Apps on This Tunnel (optional):
+                    </label>
+                    {extraEndpoints.map((endpoint, index) => (
+                        <div key={index} style={{ display: 'flex', gap: '0.5em', marginBottom: '0.4em' }}>
+                            <input
+                                type="text"
+                                className="text-input"
+                                placeholder="App ID"
+                                value={endpoint.id}
+                                onChange={(e) => setExtraEndpoints(prev => prev.map((item, i) => i === index ? { ...item, id: e.target.value.trim() } : item))}
+                                disabled={isWebhookSetupActive}
+                            />
+                            <input
+                                type="password"
+                                className="text-input"
+                                placeholder="Consumer Secret"
+                                value={endpoint.consumerSecret}
+                                onChange={(e) => setExtraEndpoints(prev => prev.map((item, i) => i === index ? { ...item, consumerSecret: e.target.value } : item))}
+                                disabled={isWebhookSetupActive}
+                                style={{ flex: 1 }}
+                            />
+                            <button className="run-button" onClick={() => setExtraEndpoints(prev => prev.filter((_, i) => i !== index))} disabled={isWebhookSetupActive}>Remove</button>
+                        </div>
+                    ))}
+                    <button className="run-button" onClick={() => setExtraEndpoints(prev => [...prev, { id: '', consumerSecret: '' }])} disabled={isWebhookSetupActive}>
+                        Add App
+                    </button>
+                    <small style={{ display: 'block', marginTop: '0.5em', fontSize: '0.8em', color: 'var(--text-color-secondary)' }}>
+                        Each app is served at <code>/webhooks/&lt;App ID&gt;</code> and checked with its own secret; every other path uses the secret above. <code>/health</code> answers without a signature.
+                    </small>
+                </div>
+
                 {
                     !isWebhookSetupActive ? (
                         <button 
                             className="run-button" 
                             onClick={handleWebhookSetupClick}
-                            disabled={!ngrokToken || !consumerSecret}
+                            disabled={(!ngrokToken && !useLocalHttps) || !consumerSecret}
                         >
                             Stand up Temporary Webhook
                         </button>
                     ) : (
+                        <>
+                        <button 
+                            className="run-button" 
+                            onClick={() => handleWebhookStopClick()}
+                            style={{ marginBottom: '0.8em' }}
+                        >
+                            Stop Webhook
+                        </button>
+                        <div style={{ display: 'flex', gap: '0.5em', alignItems: 'center', marginBottom: '0.5em' }}>
+                            <select value={consoleLevel} onChange={(e) => { const level = e.target.value as LogLevel; setConsoleLevel(level); loadConsoleLog(level); }}>
+                                <option value="debug">Debug and above</option>
+                                <option value="info">Info and above</option>
+                                <option value="warn">Warnings and errors</option>
+                                <option value="error">Errors only</option>
+                            </select>
+                            <input className="text-input" placeholder="/path/to/tunnel-log.jsonl" value={logExportPath} onChange={(e) => setLogExportPath(e.target.value)} />
+                            <button className="run-button" onClick={handleExportLog} disabled={!logExportPath}>Export Log</button>
+                            {logExportStatus && <span>{logExportStatus}</span>}
+                        </div>
                         <div 
                             ref={consoleContainerRef} 
                             className="mock-console" 
@@ -270,6 +988,30 @@ const WebhooksView: React.FC<WebhooksViewProps> = (props) => {
                             ))}
                             <div ref={consoleEndRef} />
                         </div>
+                        <details className="advanced-details" style={{ marginTop: '1em' }}>
+                            <summary className="advanced-summary">Simulate Failures</summary>
+                            <div className="advanced-section-content">
+                                <div style={{ display: 'flex', flexWrap: 'wrap', gap: '0.8em', alignItems: 'center' }}>
+                                    <select value={policyEndpointId} onChange={(e) => loadResponsePolicy(e.target.value)}>
+                                        <option value="">Default endpoint</option>
+                                        {extraEndpoints.filter(endpoint => endpoint.id).map(endpoint => (
+                                            <option key={endpoint.id} value={endpoint.id}>/webhooks/{endpoint.id}</option>
+                                        ))}
+                                    </select>
+                                    <label>Status <input className="text-input" style={{ width: '5em' }} placeholder="200" value={responsePolicy.status ?? ''} onChange={(e) => setResponsePolicy({ ...responsePolicy, status: parseInt(e.target.value, 10) || null })} /></label>
+                                    <label>Delay (ms) <input className="text-input" style={{ width: '6em' }} value={responsePolicy.delayMs} onChange={(e) => setResponsePolicy({ ...responsePolicy, delayMs: parseInt(e.target.value, 10) || 0 })} /></label>
+                                    <label>Fail every <input className="text-input" style={{ width: '4em' }} placeholder="-" value={responsePolicy.failEvery ?? ''} onChange={(e) => setResponsePolicy({ ...responsePolicy, failEvery: parseInt(e.target.value, 10) || null })} /> deliveries</label>
+                                    <label><input type="checkbox" checked={responsePolicy.dropConnection} onChange={(e) => setResponsePolicy({ ...responsePolicy, dropConnection: e.target.checked })} /> Drop connections</label>
+                                    <label><input type="checkbox" checked={responsePolicy.wrongCrc} onChange={(e) => setResponsePolicy({ ...responsePolicy, wrongCrc: e.target.checked })} /> Wrong CRC response</label>
+                                </div>
+                                <div style={{ display: 'flex', gap: '0.5em', marginTop: '0.8em', alignItems: 'center' }}>
+                                    <button className="run-button" onClick={() => applyResponsePolicy(responsePolicy)}>Apply</button>
+                                    <button className="run-button" onClick={() => applyResponsePolicy(DEFAULT_RESPONSE_POLICY)}>Answer Normally</button>
+                                    {policyStatus && <span>{policyStatus}</span>}
+                                </div>
+                            </div>
+                        </details>
+                        </>
                     )
                 }
                 {setupError && !isWebhookSetupActive && (
@@ -278,9 +1020,41 @@ const WebhooksView: React.FC<WebhooksViewProps> = (props) => {
                     </div>
                 )}
 
-                {webhookUrl && isWebhookSetupActive && (
+                {urlChangeNotice && (
+                    <div className="error-message" style={{ marginTop: '1em' }}>
+                        {urlChangeNotice}
+                        <button className="run-button" style={{ marginLeft: '0.8em' }} onClick={() => setUrlChangeNotice(null)}>Dismiss</button>
+                    </div>
+                )}
+
+                {runningTunnels.length > 0 && (
+                    <div style={{ marginTop: '1.5em' }}>
+                        <h4>Tunnels: <button className="run-button" onClick={refreshRunningTunnels}>Refresh</button></h4>
+                        {runningTunnels.map(tunnel => (
+                            <div key={tunnel.tunnel_id} style={{ display: 'flex', alignItems: 'center', gap: '0.8em', marginBottom: '0.4em' }}>
+                                <code>{tunnel.tunnel_id}</code>
+                                <span style={{ color: tunnel.is_active ? 'var(--success-color, green)' : 'var(--text-color-secondary)' }}>
+                                    {tunnel.is_active ? (tunnel.url ?? 'Starting...') : 'Stopped'}
+                                </span>
+                                {tunnel.is_active && (
+                                    <span style={{ color: 'var(--text-color-secondary)' }}>
+                                        {tunnel.uptime_secs > 0 ? `up ${formatUptime(tunnel.uptime_secs)}` : 'reconnecting'}
+                                        {tunnel.reconnects > 0 && `, ${tunnel.reconnects} reconnect${tunnel.reconnects === 1 ? '' : 's'}`}
+                                        {tunnel.last_probe && (tunnel.last_probe.valid
+                                            ? `, CRC ok in ${tunnel.last_probe.latency_ms} ms`
+                                            : `, CRC failed: ${tunnel.last_probe.problems.join('; ')}`)}
+                                        {tunnel.deliveries > 0 && `, ${tunnel.deliveries} deliver${tunnel.deliveries === 1 ? 'y' : 'ies'} (${tunnel.duplicates} duplicate${tunnel.duplicates === 1 ? '' : 's'}, ${(100 * tunnel.duplicates / tunnel.deliveries).toFixed(1)}%)`}
+                                    </span>
+                                )}
+                                <button className="run-button" onClick={() => handleWebhookStopClick(tunnel.tunnel_id)}>Stop</button>
+                            </div>
+                        ))}
+                    </div>
+                )}
+
+                {((webhookUrl && isWebhookSetupActive) || runningTunnels.length > 0) && (
                     <div className="received-webhooks-section" style={{ marginTop: '1.5em' }}>
-                        <h4>Received Webhooks (Listening at {webhookUrl}):</h4>
+                        <h4>Received Webhooks{webhookUrl && isWebhookSetupActive && ` (Listening at ${webhookUrl})`}:</h4>
                         {receivedWebhooks.length === 0 ? (
                             <p style={{ fontStyle: 'italic', color: 'var(--text-color-secondary)' }}>Waiting for incoming requests...</p>
                         ) : (
@@ -288,9 +1062,17 @@ const WebhooksView: React.FC<WebhooksViewProps> = (props) => {
                                 {receivedWebhooks.map((hook, index) => (
                                     <details key={index} className="webhook-item" style={{ marginBottom: '1em', padding: '0.8em', background: 'var(--background-color-secondary)', borderRadius: '4px' }}>
                                         <summary style={{ cursor: 'pointer', fontWeight: '500' }}>
-                                            {hook.method} {hook.uri} 
+                                            <code style={{ marginRight: '0.5em' }}>{hook.endpoint_id ? `${hook.tunnel_id}/${hook.endpoint_id}` : hook.tunnel_id}</code>
+                                            {hook.method} {hook.uri} {hook.activity?.event_type && `· ${hook.activity.event_type}`}
+                                            {hook.duplicate && <span style={{ marginLeft: '0.8em', fontSize: '0.85em', color: 'var(--warning-color, orange)' }}>Duplicate</span>}
+                                            {hook.replay_job_id && <span style={{ marginLeft: '0.8em', fontSize: '0.85em' }}>Replayed (job {hook.replay_job_id})</span>}
+                                            <span style={{ marginLeft: '0.8em', fontSize: '0.85em', color: hook.signature_valid ? 'var(--success-color, green)' : 'var(--error-color, red)' }}>
+                                                {hook.signature_valid ? 'Signature valid' : 'Signature missing or invalid'}
+                                            </span>
                                         </summary>
                                         <div style={{ marginTop: '0.8em', fontSize: '0.9em' }}>
+                                            {renderActivity(hook.activity)}
+                                            {renderForward(hook.forward)}
                                             <h5>Headers:</h5>
                                             <pre style={{ whiteSpace: 'pre-wrap', wordBreak: 'break-all', background: 'var(--code-background)', padding: '0.5em', borderRadius: '3px' }}>
                                                 {JSON.stringify(hook.headers, null, 2)}
@@ -306,6 +1088,169 @@ const WebhooksView: React.FC<WebhooksViewProps> = (props) => {
                         )}
                     </div>
                 )}
+
+                <details className="advanced-details" style={{ marginTop: '1.5em' }}>
+                    <summary className="advanced-summary">Check CRC</summary>
+                    <div className="advanced-section-content">
+                        <p>Sends a CRC challenge to your webhook server using the consumer secret above, as X does before registering it.</p>
+                        <div style={{ display: 'flex', gap: '0.5em', marginBottom: '0.8em' }}>
+                            <input className="text-input" placeholder="https://example.com/webhook" value={crcCheckUrl} onChange={(e) => setCrcCheckUrl(e.target.value)} style={{ flex: 1 }} />
+                            <button className="run-button" onClick={runCrcCheck} disabled={!crcCheckUrl || !consumerSecret}>Check</button>
+                        </div>
+                        {crcCheckError && <div style={{ color: 'var(--error-color, red)' }}>{crcCheckError}</div>}
+                        {crcCheckReport && (
+                            <div>
+                                <div style={{ fontWeight: '500', color: crcCheckReport.valid ? 'var(--success-color, green)' : 'var(--error-color, red)' }}>
+                                    {crcCheckReport.valid ? 'CRC check passed' : 'CRC check failed'} · HTTP {crcCheckReport.status ?? '-'} · {crcCheckReport.latency_ms} ms
+                                </div>
+                                <ul style={{ margin: '0.5em 0', paddingLeft: '1.2em' }}>
+                                    {crcCheckReport.problems.map((problem, index) => <li key={index}>{problem}</li>)}
+                                </ul>
+                                <pre style={{ whiteSpace: 'pre-wrap', wordBreak: 'break-all', background: 'var(--code-background)', padding: '0.5em', borderRadius: '3px' }}>
+                                    {`GET ${crcCheckReport.url}\nExpected: ${crcCheckReport.expected_token}\nReceived: ${crcCheckReport.response_token ?? '(none)'}\nContent-Type: ${crcCheckReport.content_type ?? '(none)'}`}
+                                </pre>
+                            </div>
+                        )}
+                    </div>
+                </details>
+
+                <details className="advanced-details" style={{ marginTop: '1.5em' }}>
+                    <summary className="advanced-summary">Set Up Account Activity</summary>
+                    <div className="advanced-section-content">
+                        <p>Starts the tunnel, registers it with <code>POST /2/webhooks</code>, waits for X to accept the CRC response and subscribes the active app's owner. Steps already done are undone if one fails.</p>
+                        <div style={{ display: 'flex', gap: '0.5em', marginBottom: '0.8em' }}>
+                            <button className="run-button" onClick={runAccountActivitySetup} disabled={setupRunning || !ngrokToken || !consumerSecret}>Set Up</button>
+                            <button className="run-button" onClick={runAccountActivityTeardown} disabled={setupRunning || !accountActivitySetup}>Tear Down</button>
+                        </div>
+                        {accountActivitySetup && (
+                            <div>Webhook <code>{accountActivitySetup.webhookId}</code> at <code>{accountActivitySetup.webhookUrl}</code>{accountActivitySetup.userId && <> · subscribed user <code>{accountActivitySetup.userId}</code></>}</div>
+                        )}
+                        {accountActivityError && <div style={{ color: 'var(--error-color, red)' }}>{accountActivityError}</div>}
+                        <ul style={{ margin: '0.5em 0', paddingLeft: '1.2em' }}>
+                            {setupSteps.map((progress, index) => (
+                                <li key={index}>{progress.step.replace(/_/g, ' ')}: {progress.status.replace(/_/g, ' ')}{progress.message && ` (${progress.message})`}</li>
+                            ))}
+                        </ul>
+                    </div>
+                </details>
+
+                <details className="advanced-details" style={{ marginTop: '1.5em' }}>
+                    <summary className="advanced-summary">Replay Past Activity</summary>
+                    <div className="advanced-section-content">
+                        <p>Asks X to redeliver the webhook's events from a past window (e.g. after your consumer was down). Redelivered events are tagged with the job below and can be filtered in Stored Events.</p>
+                        <div style={{ display: 'flex', gap: '0.5em', marginBottom: '0.8em' }}>
+                            <input className="text-input" type="datetime-local" value={replayFrom} onChange={(e) => setReplayFrom(e.target.value)} />
+                            <input className="text-input" type="datetime-local" value={replayTo} onChange={(e) => setReplayTo(e.target.value)} />
+                            <button className="run-button" onClick={requestActivityReplay} disabled={!replayFrom || !replayTo || !accountActivitySetup}>Request Replay</button>
+                        </div>
+                        {replayJobError && <div style={{ color: 'var(--error-color, red)' }}>{replayJobError}</div>}
+                        <ul style={{ margin: '0.5em 0', paddingLeft: '1.2em' }}>
+                            {replayJobs.map(job => (
+                                <li key={job.jobId}>
+                                    Job <code>{job.jobId}</code> · {new Date(job.from).toLocaleString()} to {new Date(job.to).toLocaleString()} · {job.state}
+                                    {job.description && ` (${job.description})`} · {job.replayed} replayed
+                                    <button className="run-button" style={{ marginLeft: '0.5em' }} onClick={() => setStoredReplayJobId(job.jobId)}>Filter Stored Events</button>
+                                </li>
+                            ))}
+                        </ul>
+                    </div>
+                </details>
+
+                <details className="advanced-details" style={{ marginTop: '1.5em' }}>
+                    <summary className="advanced-summary">Generate Test Events</summary>
+                    <div className="advanced-section-content">
+                        <p>POSTs signed synthetic Account Activity payloads using the consumer secret above.</p>
+                        <div style={{ display: 'flex', gap: '0.5em', marginBottom: '0.8em', flexWrap: 'wrap' }}>
+                            <select value={generatorKind} onChange={(e) => setGeneratorKind(e.target.value)}>
+                                <option value="tweet_create">Tweet create</option>
+                                <option value="favorite">Like</option>
+                                <option value="follow">Follow</option>
+                                <option value="direct_message">Direct message</option>
+                                <option value="tweet_delete">Tweet delete</option>
+                            </select>
+                            <input className="text-input" placeholder="For user id" value={generatorUserId} onChange={(e) => setGeneratorUserId(e.target.value)} style={{ flex: 1 }} />
+                            <input className="text-input" placeholder={webhookUrl ?? 'Target webhook URL'} value={generatorTarget} onChange={(e) => setGeneratorTarget(e.target.value)} style={{ flex: 2 }} />
+                        </div>
+                        <div style={{ display: 'flex', gap: '0.5em', marginBottom: '0.8em', flexWrap: 'wrap', alignItems: 'center' }}>
+                            <select value={generatorLoad} onChange={(e) => setGeneratorLoad(e.target.value)}>
+                                <option value="once">Once</option>
+                                <option value="steady">Steady</option>
+                                <option value="bursty">Bursty (5 bursts, 2 s apart)</option>
+                            </select>
+                            {generatorLoad !== 'once' && (
+                                <input className="text-input" type="number" min="1" max={generatorLoad === 'bursty' ? 100 : undefined} placeholder={generatorLoad === 'bursty' ? 'Events per burst' : 'Total events'} value={generatorCount} onChange={(e) => setGeneratorCount(e.target.value)} style={{ width: '8em' }} />
+                            )}
+                            {generatorLoad === 'steady' && (
+                                <input className="text-input" type="number" min="0.1" step="0.1" placeholder="Per second" value={generatorRate} onChange={(e) => setGeneratorRate(e.target.value)} style={{ width: '8em' }} />
+                            )}
+                            {!generatorRunning ? (
+                                <button className="run-button" onClick={runGenerator} disabled={!consumerSecret || !(generatorTarget || webhookUrl)}>Send</button>
+                            ) : (
+                                <button className="run-button" onClick={() => invoke('stop_webhook_generator')}>Stop</button>
+                            )}
+                        </div>
+                        {generatorStatus && <div>{generatorStatus}</div>}
+                    </div>
+                </details>
+
+                <details className="advanced-details" style={{ marginTop: '1.5em' }}>
+                    <summary className="advanced-summary">Stored Events ({storedTotal})</summary>
+                    <div className="advanced-section-content">
+                        <div style={{ display: 'flex', gap: '0.5em', marginBottom: '0.8em', flexWrap: 'wrap' }}>
+                            <input className="text-input" placeholder="Search URI, headers and body" value={storedSearch} onChange={(e) => setStoredSearch(e.target.value)} style={{ flex: 2 }} />
+                            <input className="text-input" placeholder="Event type (e.g. tweet_create_events)" value={storedEventType} onChange={(e) => setStoredEventType(e.target.value)} style={{ flex: 1 }} />
+                            <input className="text-input" placeholder="For user id" value={storedUserId} onChange={(e) => setStoredUserId(e.target.value)} style={{ flex: 1 }} />
+                            <label style={{ display: 'flex', alignItems: 'center', gap: '0.3em' }}>
+                                <input type="checkbox" checked={storedDuplicatesOnly} onChange={(e) => setStoredDuplicatesOnly(e.target.checked)} />
+                                Duplicates only
+                            </label>
+                            <input className="text-input" placeholder="Replay job id" value={storedReplayJobId} onChange={(e) => setStoredReplayJobId(e.target.value)} style={{ flex: 1 }} />
+                            <button className="run-button" onClick={() => loadStoredEvents(0)}>Search</button>
+                            <button className="run-button" onClick={() => deleteStoredEvents(null)} disabled={storedTotal === 0}>Delete Matching</button>
+                        </div>
+                        <div style={{ display: 'flex', gap: '0.5em', marginBottom: '0.8em', flexWrap: 'wrap' }}>
+                            <input className="text-input" placeholder="Replay to URL (e.g. http://localhost:3000/webhook)" value={replayUrl} onChange={(e) => setReplayUrl(e.target.value)} style={{ flex: 2 }} />
+                            <input className="text-input" type="password" placeholder="Re-sign with consumer secret (optional)" value={replaySecret} onChange={(e) => setReplaySecret(e.target.value)} style={{ flex: 1 }} />
+                            <input className="text-input" type="number" min="0" placeholder="Interval (ms)" value={replayIntervalMs} onChange={(e) => setReplayIntervalMs(e.target.value)} style={{ width: '8em' }} />
+                            <button className="run-button" onClick={() => replayStoredEvents(storedEvents.map(event => event.id))} disabled={!replayUrl || storedEvents.length === 0}>Replay Page</button>
+                        </div>
+                        {storedError && <div style={{ color: 'var(--error-color, red)', marginBottom: '0.8em' }}>{storedError}</div>}
+                        {storedEvents.length === 0 ? (
+                            <p style={{ fontStyle: 'italic', color: 'var(--text-color-secondary)' }}>No stored events.</p>
+                        ) : (
+                            <div className="webhook-list" style={{ maxHeight: '400px', overflowY: 'auto', border: '1px solid var(--border-color)', borderRadius: '4px', padding: '0.5em' }}>
+                                {storedEvents.map((event) => (
+                                    <details key={event.id} className="webhook-item" style={{ marginBottom: '1em', padding: '0.8em', background: 'var(--background-color-secondary)', borderRadius: '4px' }}>
+                                        <summary style={{ cursor: 'pointer', fontWeight: '500' }}>
+                                            {new Date(event.received_at).toLocaleString()} · {event.event_type ?? `${event.method} ${event.uri}`}
+                                            {event.for_user_id && ` · user ${event.for_user_id}`}
+                                            {event.replay_job_id && ` · replayed (job ${event.replay_job_id})`}
+                                            {event.duplicate && ' · duplicate'}
+                                            {!event.signature_valid && <span style={{ marginLeft: '0.8em', color: 'var(--error-color, red)' }}>Signature missing or invalid</span>}
+                                        </summary>
+                                        <div style={{ marginTop: '0.8em', fontSize: '0.9em' }}>
+                                            {renderActivity(event.activity)}
+                                            {renderForward(event.forward)}
+                                            <pre style={{ whiteSpace: 'pre-wrap', wordBreak: 'break-all', background: 'var(--code-background)', padding: '0.5em', borderRadius: '3px' }}>
+                                                {decodeBody(event.body) || "[Empty Body]"}
+                                            </pre>
+                                            {replayOutcomes[event.id] && renderForward(replayOutcomes[event.id].result)}
+                                            <div style={{ display: 'flex', gap: '0.5em' }}>
+                                                <button className="run-button" onClick={() => replayStoredEvents([event.id])} disabled={!replayUrl}>Replay</button>
+                                                <button className="run-button" onClick={() => deleteStoredEvents([event.id])}>Delete</button>
+                                            </div>
+                                        </div>
+                                    </details>
+                                ))}
+                            </div>
+                        )}
+                        <div style={{ display: 'flex', gap: '0.5em', marginTop: '0.8em', alignItems: 'center' }}>
+                            <button className="run-button" onClick={() => loadStoredEvents(Math.max(0, storedOffset - STORED_EVENTS_PAGE_SIZE))} disabled={storedOffset === 0}>Previous</button>
+                            <span>{storedTotal === 0 ? 0 : storedOffset + 1}–{storedOffset + storedEvents.length} of {storedTotal}</span>
+                            <button className="run-button" onClick={() => loadStoredEvents(storedOffset + STORED_EVENTS_PAGE_SIZE)} disabled={storedOffset + storedEvents.length >= storedTotal}>Next</button>
+                        </div>
+                    </div>
+                </details>
             </div>
         </details>
     );